@@ -4,12 +4,7 @@
 #include <array>
 #include <deque>
 #include <cstdint>
-#include <iostream>
-
-// Debug macro for APU
-#ifndef GB_APU_DEBUG
-#define GB_APU_DEBUG 1
-#endif
+#include "save_state.h"
 
 // APU Register Addresses
 #define NR10 0xFF10
@@ -34,6 +29,14 @@
 #define NR51 0xFF25
 #define NR52 0xFF26
 
+// 前端音訊設定：一次套用全部參數（不影響模擬的暫存器狀態）
+struct AudioConfig {
+    float master_gain = 1.0f;                                 // applied after NR50
+    std::array<bool, 4> channel_enabled = {true, true, true, true}; // mute CH1-CH4 in the mix only
+    float high_pass_cutoff = 0.0f;                            // Hz, 0 = off (DC blocking like the DMG output capacitor)
+    int sample_rate = 44100;                                  // output samples per second
+};
+
 class APU {
 public:
     APU();
@@ -41,10 +44,31 @@ public:
 
     void reset();
     void step(int cycles);
+    // Frame sequencer source: by default the APU divides its own cycles by 8192. The MMU switches
+    // this to DIV-APU (falling edge of DIV bit 4) and calls clock_frame_sequencer() on each edge,
+    // so length/envelope/sweep stay locked to DIV, including DIV resets.
+    void set_frame_sequencer_from_div(bool from_div) { frame_sequencer_from_div = from_div; }
+    void clock_frame_sequencer();
+
+    // Save state: registers, wave RAM, channel and frame sequencer state (queued output samples are dropped)
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
     uint8_t read_register(uint16_t address) const;
     void write_register(uint16_t address, uint8_t value);
 
+    // Mono downmix ((L + R) / 2), one float per output sample
     void get_audio_samples(float* buffer, int length);
+    // Stereo output as interleaved L/R pairs; buffer must hold frames * 2 floats.
+    // NR51 routes each channel to left/right and NR50 sets each side's master volume.
+    void get_audio_samples_stereo(float* buffer, int frames);
+
+    // Replace all output settings at once; takes effect from the next generated sample
+    void apply_audio_config(const AudioConfig& config);
+    const AudioConfig& get_audio_config() const { return audio_config; }
+
+    // Current playing frequency per channel in Hz (0 when the channel is silent).
+    // CH1/CH2: 131072/(2048-x), CH3: 65536/(2048-x), CH4: LFSR clock rate from NR43.
+    std::array<float, 4> channel_frequencies() const;
     void handle_side_effects(uint16_t address, uint8_t value, uint8_t old_reg);
 
 private:
@@ -164,14 +188,21 @@ private:
     // Frame sequencer
     int frame_counter;
     int frame_step;
+    bool frame_sequencer_from_div = false;
 
     // Sample timing
     double sample_timer;
     double cycles_per_sample;
 
-    // Audio buffer
+    // Audio buffer (interleaved L/R)
     std::deque<float> audio_fifo;
 
+    // Output settings and high-pass filter state
+    AudioConfig audio_config;
+    float high_pass_alpha = 1.0f;
+    std::array<float, 2> high_pass_prev_in = {0.0f, 0.0f};   // [0] = left, [1] = right
+    std::array<float, 2> high_pass_prev_out = {0.0f, 0.0f};
+
     // Power state tracking
     bool apu_was_off;
 
@@ -219,8 +250,8 @@ private:
     void handle_noise_length(uint8_t value);
     void handle_frequency_low(int channel_num, uint8_t value);
     void handle_wave_on_off(uint8_t value);
-    uint8_t read_wave_ram(uint16_t address) const;
-    void write_wave_ram(uint16_t address, uint8_t value);
+    uint8_t read_wave_ram(uint16_t address) const;   // 0xFF while CH3 plays (DMG, see apu.cpp)
+    void write_wave_ram(uint16_t address, uint8_t value); // ignored while CH3 plays
 
     // Sub-functions for handle_side_effects to reduce complexity
     void handle_nr11_side_effects(uint8_t value);
@@ -248,9 +279,6 @@ public:
     // Mix and push sample
     void mix_and_push_sample();
 
-    // Debug helpers
-    // When true, only log length-related events (useful for Blargg tests)
-    bool debug_len_only{false};
     // Unit test helpers (debug only)
     void debug_set_frame_step(int fs) { frame_step = fs; }
     void debug_set_length_counter(int channel, int value) {
@@ -273,21 +301,6 @@ public:
     }
     int debug_get_frame_counter() const { return frame_counter; }
     int debug_get_frame_step() const { return frame_step; }
-    void debug_log(const char* op, uint16_t addr, uint8_t val) const {
-        if (GB_APU_DEBUG && !this->debug_len_only) {
-            std::cout << "[APU] " << op << " 0x" << std::hex << addr << " = 0x" << (int)val << std::dec << std::endl;
-        }
-    }
-
-    uint8_t debug_read(uint16_t addr, uint8_t val) const {
-        if (GB_APU_DEBUG && !this->debug_len_only) {
-            std::cout << "[APU] RD 0x" << std::hex << addr << " -> 0x" << (int)val << std::dec << std::endl;
-        }
-        return val;
-    }
-
-    // Enable/disable the len-only debug filter
-    void set_debug_len_only(bool v) { debug_len_only = v; }
 
     // Register table declaration (defined in apu.cpp)
     static const RegisterDescriptor reg_table[0x17];
@@ -3,6 +3,7 @@
 
 #include <cstdint>
 #include <array>
+#include <vector>
 
 class MMU; // Forward declaration
 
@@ -22,13 +23,21 @@ public:
                       std::array<uint32_t, 160 * 144>& framebuffer,
                       std::array<uint8_t, 160 * 144>& bgwin_pixel_ids);
 
-    // Get pixel color from tile data
-    uint8_t get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y) const;
+    // Render the whole 256x256 BG tile map (ignores SCX/SCY and the 160x144 viewport)
+    std::vector<uint32_t> render_full_background(MMU& mmu, uint8_t lcdc, uint8_t bgp) const;
+
+    // Get pixel color from tile data (bank 1 only exists in CGB mode)
+    uint8_t get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y, uint8_t bank = 0) const;
 
     // Get color from palette
     uint32_t get_color(uint8_t color_id, uint8_t palette) const;
 
+    // ARGB colors for DMG shades 0-3 (set by PPU::set_palette; the PPU constructor installs its default)
+    void set_shades(const std::array<uint32_t, 4>& colors) { shades = colors; }
+
 private:
+    std::array<uint32_t, 4> shades{};
+
     // Window debug state
     static int window_debug_lines_printed;
     static int last_frame_ly;
@@ -3,13 +3,30 @@
 
 #include <cstdint>
 #include "mmu.h"
-#include <fstream>
+#include <ostream>
+#include <array>
+#include <string>
+#include <utility>
+#include <unordered_set>
+#include "save_state.h"
 
 // CPU debug logging. Set to 1 to enable per-step logging for the CPU.
 #ifndef GB_CPU_DEBUG
 #define GB_CPU_DEBUG 0
 #endif
 
+// CPU state just before an instruction executes (one record per executed instruction)
+struct TraceRecord {
+    uint16_t pc;
+    uint8_t opcode;
+    uint16_t af, bc, de, hl, sp;
+    std::array<uint8_t, 4> pcmem; // bytes at PC..PC+3 (pcmem[0] == opcode)
+
+    // gameboy-doctor format:
+    // "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0100 PCMEM:00,C3,13,02"
+    std::string to_doctor_line() const;
+};
+
 class CPU {
 public:
     CPU(MMU& mmu);
@@ -76,19 +93,53 @@ public:
     void handle_interrupt(uint8_t interrupt_type);
     void execute_instruction(uint8_t opcode);
     int execute_instruction_with_cycles(uint8_t opcode); // Returns cycles
-    void execute_load_instructions(uint8_t opcode); // Load instructions (switch-case)
-    void execute_load_instructions_map(uint8_t opcode); // Load instructions (map-based)
+    void execute_load_instructions(uint8_t opcode); // Load instructions
     void sync_f_register(); // Sync F register from flags
     void load_flags_from_f(); // Load flags from F register
     
+    // Current state as a trace record, and the same in gameboy-doctor trace format
+    TraceRecord trace_record();
+    std::string trace_line() { return trace_record().to_doctor_line(); }
+
+    // Per-instruction trace: when set, step() writes one gameboy-doctor line to the sink
+    // before each instruction it executes (HALT idle steps write nothing). nullptr disables.
+    // The sink is not owned and must outlive the CPU or be cleared first.
+    void set_trace_sink(std::ostream* sink) { trace_sink = sink; }
+
+    // Disassemble the instruction at pc: full text with operands (e.g. "LD A,$42", JR shows
+    // the absolute target) and its length in bytes. CB-prefixed opcodes are included.
+    // Reads go through MMU::peek_byte, so watchpoints are not triggered.
+    static std::pair<std::string, uint8_t> disassemble(MMU& mmu, uint16_t pc);
+
+    // Save state: registers, flags, IME/EI delay and HALT state
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
+
     // Timing test quick mode
     void set_timing_test_mode(bool on) { timing_test_mode = on; }
 
+    // Debugger stops. A breakpoint stops step() before the instruction at that PC runs
+    // (step() returns 0 cycles); the next step() executes it. A watchpoint reports after
+    // the instruction that read/wrote the address. Check stop_reason() after each step().
+    enum class StopReason { None, Breakpoint, Watchpoint };
+    void add_breakpoint(uint16_t address) { breakpoints.insert(address); }
+    void remove_breakpoint(uint16_t address) { breakpoints.erase(address); }
+    void clear_breakpoints() { breakpoints.clear(); }
+    void add_watchpoint(uint16_t address, bool on_write) { mmu.add_watchpoint(address, on_write); }
+    void remove_watchpoint(uint16_t address, bool on_write) { mmu.remove_watchpoint(address, on_write); }
+    void clear_watchpoints() { mmu.clear_watchpoints(); }
+    StopReason stop_reason() const { return last_stop; }
+    uint16_t watchpoint_address() const { return last_watch_address; }
+
+    // Research hook: replace the T-cycle cost of a base (non-CB) opcode, including both
+    // branch outcomes of conditional instructions. Cleared by clear_cycle_overrides().
+    void override_cycles(uint8_t opcode, uint8_t cycles) { cycle_overrides[opcode] = cycles; }
+    void clear_cycle_override(uint8_t opcode) { cycle_overrides[opcode] = -1; }
+    void clear_cycle_overrides() { cycle_overrides.fill(-1); }
+
 private:
     MMU& mmu;
-    std::ofstream log_file;
-    // Instruction cycle instrumentation log (used for instr_timing analysis)
-    std::ofstream instr_cycle_log;
+    std::ostream* trace_sink = nullptr;
 
     // Helper functions
     void add(uint8_t value);
@@ -99,6 +150,10 @@ private:
     void adc(uint8_t value);
     void sbc(uint8_t value);
     void cp(uint8_t value);
+    void daa();
+    void cpl();
+    void scf();
+    void ccf();
     void inc(uint8_t& reg);
     void dec(uint8_t& reg);
     void rlca();
@@ -106,6 +161,9 @@ private:
     void rla();
     void rra();
     void rst(uint8_t addr);
+    void push16(uint16_t value); // decrement, write high byte, decrement, write low byte
+    // Requested and enabled interrupts (IE & IF); bits 5-7 of either register never dispatch or wake HALT
+    uint8_t pending_interrupts() { return mmu.read_byte(0xFFFF) & mmu.read_byte(0xFF0F) & 0x1F; }
     void add_hl(uint16_t value);
     void execute_cb_instruction(uint8_t cb_opcode);
     void execute_stop_instruction();
@@ -130,6 +188,16 @@ private:
     void res(uint8_t bit, uint8_t& reg);
     void set(uint8_t bit, uint8_t& reg);
 
+    // Debugger state
+    std::unordered_set<uint16_t> breakpoints;
+    int resume_breakpoint_pc = -1; // PC we just stopped at; the next step() runs it
+    StopReason last_stop = StopReason::None;
+    uint16_t last_watch_address = 0;
+
+    // Per-opcode cycle overrides (-1 = use the built-in timing)
+    std::array<int, 256> cycle_overrides = make_no_overrides();
+    static std::array<int, 256> make_no_overrides() { std::array<int, 256> a{}; a.fill(-1); return a; }
+
     // --- Quick micro-step helpers (Route A) ---
     bool timing_test_mode = false;
     int timing_burned_tcycles = 0; // consumed inside instruction
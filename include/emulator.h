@@ -6,6 +6,58 @@
 #include <SDL3/SDL.h>
 #include <vector>
 #include <string>
+#include <array>
+#include <optional>
+#include <chrono>
+
+class Emulator;
+
+// 每幀結束時呼叫的擴充點（金手指、覆蓋層、輸入錄製等）
+class FrameHook {
+public:
+    virtual ~FrameHook() = default;
+    virtual void on_frame(Emulator& emulator) = 0;
+};
+
+// 前端按鍵自動連發設定（不影響硬體暫存器本身的行為），單位為幀
+struct InputConfig {
+    int repeat_delay = 0; // frames a button must be held before repeating (0 = auto-repeat off)
+    int repeat_rate = 0;  // frames per repeat cycle once repeating (minimum 2: one released, one pressed)
+};
+
+// 畫面外框（SGB 邊框或裝飾框）：遊戲畫面 160x144 貼在 (game_x, game_y)
+struct BorderImage {
+    int width = 256;               // SGB border size by default
+    int height = 224;
+    int game_x = 48;               // top-left of the game area inside the border
+    int game_y = 40;
+    std::vector<uint32_t> pixels;  // ARGB, row-major, width * height
+};
+
+// 與參考 trace（例如 gameboy-doctor 日誌）逐指令比對的結果
+struct TraceDivergence {
+    bool diverged = false;
+    size_t line = 0;      // 1-based line in the reference trace where execution differed
+    std::string expected; // reference line
+    std::string actual;   // our state before executing the same instruction
+    std::string previous; // last matching line (the instruction that produced the bad state)
+};
+
+// 卡死/當機的啟發式判斷結果（health_check），供前端提示使用者
+enum class EmulationHealth {
+    Ok,
+    SuspectedHang,    // PC stayed inside a few bytes (tight loop, or HALT that never wakes) for HANG_FRAMES frames
+    RunawayExecution, // ran a long stretch of 0x00/0xFF opcodes (blank memory) or executed from OAM/unusable/I/O space
+    StackOutOfRange   // SP points into ROM (0x0000-0x7FFF) or OAM/unusable/I/O space (0xFE00-0xFF7F)
+};
+
+// 目前 PC 上的指令（不執行）：供除錯器顯示「下一條指令」
+struct PeekedInstruction {
+    uint16_t pc = 0;
+    uint8_t opcode = 0;   // first byte (0xCB for CB-prefixed instructions)
+    uint8_t length = 0;   // bytes, including operands
+    std::string mnemonic; // full text from CPU::disassemble, e.g. "LD A,$42" or "BIT 7,H"
+};
 
 class Emulator {
 public:
@@ -13,30 +65,185 @@ public:
     ~Emulator();
 
     bool initialize();
-    bool load_rom(const std::string& rom_path);
-    void run();
+    // Logo/header checksum mismatches only log a warning by default (homebrew, patched ROMs);
+    // with strict_header the ROM is rejected instead and the current state is left untouched
+    bool load_rom(const std::string& rom_path, bool strict_header = false);
+    // Main loop until the window closes (or max_frames). Needs initialize(); without a window
+    // (initialize() not called or failed) it logs an error and returns false at once unless headless.
+    // Returns true once the loop has run. load_rom() itself needs no window, so ROMs can be
+    // loaded before or after initialize().
+    bool run();
     void shutdown();
 
     // Expose PPU LCD start offset configuration for timing experiments
     void set_ppu_lcd_start_offset(uint16_t offset);
 
+    // Execute one CPU instruction and advance PPU/APU by the same T-cycles; returns T-cycles
+    // (at normal speed; in CGB double speed an instruction's CPU cycles count half)
+    int step();
+    // Headless frame step: run until LY enters 144 (start of VBlank) and return the framebuffer
+    // (ARGB, 160x144). No SDL or wall-clock pacing; with the LCD off it runs one frame's worth of cycles.
+    // Frame hooks run at the end, as in run(). Returns early (without hooks) when a breakpoint/watchpoint stops the CPU.
+    const std::array<uint32_t, 160 * 144>& run_frame();
+
+    // Emulated time since reset/ROM load: T-cycles stepped, and those cycles in seconds at 4194304 Hz
+    uint64_t emulated_cycles() const { return total_cycles; }
+    std::chrono::duration<double> emulated_time() const {
+        return std::chrono::duration<double>(static_cast<double>(total_cycles) / CPU_CLOCK_HZ);
+    }
+    // Step until the byte at addr equals value (e.g. a test ROM's status byte).
+    // Returns elapsed T-cycles, or -1 if max_cycles ran out first or a breakpoint/watchpoint stopped it.
+    int64_t run_until_memory(uint16_t addr, uint8_t value, uint64_t max_cycles);
+    // Heuristic crash/hang detection from the instructions stepped so far; runaway execution
+    // is sticky until the next reset, ROM load or state load
+    EmulationHealth health_check() const;
+
+    // Golden trace comparison: load a reference log (one gameboy-doctor line per instruction),
+    // then step while comparing CPU::trace_line() to each line and stop at the first mismatch.
+    bool load_reference_trace(const std::string& path);
+    void set_reference_trace(const std::vector<std::string>& lines) { reference_trace = lines; }
+    TraceDivergence run_against_reference();
+    // Capture a gameboy-doctor trace (one line per executed instruction); nullptr stops it
+    void set_trace_sink(std::ostream* sink) { cpu.set_trace_sink(sink); }
+
     // Optional: limit how many frames to run before exiting (0 = unlimited)
     void set_max_frames(int frames) { max_frames = frames; }
 
+    // Audio latency budget in sample frames. It sizes the SDL device buffer (audio_buffer_frames(),
+    // requested when initialize() opens the device) and caps the stream queue; samples beyond the
+    // cap are trimmed from each frame instead of queuing up. Small values lower latency but underrun
+    // more easily; large values do the opposite.
+    void set_audio_latency_samples(int samples) { audio_latency_samples = samples > 0 ? samples : 1; }
+    int get_audio_latency_samples() const { return audio_latency_samples; }
+    // Device buffer for the current budget: the largest power of two up to half of it (at least 64),
+    // so the stream queue always holds about one more buffer
+    int audio_buffer_frames() const;
+
+    // Reconfigure audio output (gain, channel mutes, high-pass, sample rate) in one call
+    void apply_audio_config(const AudioConfig& config);
+    const AudioConfig& get_audio_config() const { return mmu.get_apu().get_audio_config(); }
+
+    // Toggleable workarounds for emulator-detection tricks (open-bus value, RAM fill)
+    void apply_compat_shims(const CompatShims& shims) { mmu.apply_compat_shims(shims); }
+    const CompatShims& get_compat_shims() const { return mmu.get_compat_shims(); }
+
+    // Single accuracy switch; AccuracyLevel documents what each level enables
+    void set_accuracy(AccuracyLevel level) { mmu.set_accuracy(level); }
+    AccuracyLevel get_accuracy() const { return mmu.get_accuracy(); }
+
+    // Held-button auto-repeat for frontends (menus that poll the joypad every frame)
+    void set_input_config(const InputConfig& config) { input_config = config; }
+    const InputConfig& get_input_config() const { return input_config; }
+    // Press/release a joypad button (bit 0-7: Right, Left, Up, Down, A, B, Select, Start)
+    void set_button(int bit, bool pressed);
+
+    // Whole 256x256 background map, independent of scroll (for debugger views)
+    std::vector<uint32_t> render_full_background() { return mmu.get_ppu().render_full_background(mmu); }
+    // Sprites that would render on a scanline, in priority order (for sprite-flicker debugging)
+    std::vector<SpriteRenderer::Sprite> sprites_on_line(uint8_t line) { return mmu.get_ppu().sprites_on_line(mmu, line); }
+
+    // 16-bit register pairs for debuggers (AF is assembled from the live flags; F low nibble is always 0)
+    uint16_t af() const {
+        return static_cast<uint16_t>((cpu.A << 8) | (cpu.zero_flag ? 0x80 : 0) | (cpu.subtract_flag ? 0x40 : 0) |
+                                     (cpu.half_carry_flag ? 0x20 : 0) | (cpu.carry_flag ? 0x10 : 0));
+    }
+    uint16_t bc() const { return cpu.BC; }
+    uint16_t de() const { return cpu.DE; }
+    uint16_t hl() const { return cpu.HL; }
+    uint16_t sp() const { return cpu.SP; }
+    uint16_t pc() const { return cpu.PC; }
+    void set_af(uint16_t value) { cpu.AF = value & 0xFFF0; cpu.load_flags_from_f(); }
+    void set_bc(uint16_t value) { cpu.BC = value; }
+    void set_de(uint16_t value) { cpu.DE = value; }
+    void set_hl(uint16_t value) { cpu.HL = value; }
+    void set_sp(uint16_t value) { cpu.SP = value; }
+    void set_pc(uint16_t value) { cpu.PC = value; }
+
+    // Debugger: breakpoints stop before the instruction at a PC, watchpoints after an access.
+    // step() returns 0 at a breakpoint; run()/run_frame()/run_until_memory() stop on either.
+    void add_breakpoint(uint16_t address) { cpu.add_breakpoint(address); }
+    void remove_breakpoint(uint16_t address) { cpu.remove_breakpoint(address); }
+    void clear_breakpoints() { cpu.clear_breakpoints(); }
+    void add_watchpoint(uint16_t address, bool on_write) { cpu.add_watchpoint(address, on_write); }
+    void remove_watchpoint(uint16_t address, bool on_write) { cpu.remove_watchpoint(address, on_write); }
+    void clear_watchpoints() { cpu.clear_watchpoints(); }
+    CPU::StopReason stop_reason() const { return cpu.stop_reason(); }
+    uint16_t watchpoint_address() const { return cpu.watchpoint_address(); }
+    // Instruction text and length at an address (see CPU::disassemble)
+    std::pair<std::string, uint8_t> disassemble(uint16_t address) { return CPU::disassemble(mmu, address); }
+    // Instruction at the current PC, without executing it or triggering watchpoints
+    PeekedInstruction peek_instruction();
+
+    // Timing experiments: override the T-cycle cost of a base opcode
+    void override_cycles(uint8_t opcode, uint8_t cycles) { cpu.override_cycles(opcode, cycles); }
+    void clear_cycle_overrides() { cpu.clear_cycle_overrides(); }
+
+    // Decoded header of the loaded ROM (title, CGB flag, MBC type, ROM/RAM size, licensee)
+    const CartridgeHeader& cartridge_header() const { return mmu.get_cartridge_header(); }
+
+    // Cartridge RAM for save editors: all banks flat (bank n at n * 0x2000), regardless of mapping
+    std::vector<uint8_t>& external_ram() { return mmu.get_external_ram(); }
+    const std::vector<uint8_t>& external_ram() const { return mmu.get_external_ram(); }
+
+    // DMG shade colors (ARGB, lightest to darkest) for the whole screen; default PPU::DEFAULT_PALETTE
+    // (green tint), PPU::GRAYSCALE_PALETTE for gray
+    void set_palette(const std::array<uint32_t, 4>& colors) { mmu.get_ppu().set_palette(colors); }
+
+    // Save states (versioned binary: "GBSS" + format version, emulated cycles, then CPU and the whole MMU).
+    // load_state() returns false and leaves the emulator unchanged if the data is invalid,
+    // from another format version, or for a cartridge with a different RAM size.
+    std::vector<uint8_t> save_state() const;
+    bool load_state(const std::vector<uint8_t>& data);
+
+    // Border composited around the 160x144 game area (nullopt removes it). Returns false and
+    // keeps the current border if the pixel count is wrong or the game area doesn't fit.
+    // Can be set before or after initialize(); the window is resized to keep the border's aspect.
+    bool set_border(std::optional<BorderImage> image);
+    const std::optional<BorderImage>& get_border() const { return border; }
+    // What the window shows: the border with the current frame pasted in, or just the frame
+    // (160x144) without a border. Size is composited_width() x composited_height().
+    std::vector<uint32_t> composited_frame() const;
+    int composited_width() const { return border ? border->width : 160; }
+    int composited_height() const { return border ? border->height : 144; }
+
     // Save current framebuffer to a simple binary PPM file (P6)
     bool save_framebuffer_ppm(const std::string& path) const;
+    // Current frame as raw Game Boy 2bpp tile data (360 tiles, 5760 bytes)
+    std::vector<uint8_t> export_frame_2bpp() const { return mmu.get_ppu().export_frame_2bpp(); }
 
     // Set headless mode (no SDL window, for testing)
     void set_headless(bool headless) { this->headless = headless; }
     // Expose APU access for unit tests/debugging
     APU& debug_get_apu() { return mmu.get_apu(); }
+    // Per-channel playing frequency in Hz (for music visualizers)
+    std::array<float, 4> channel_frequencies() const { return mmu.get_apu().channel_frequencies(); }
     // Expose PPU access for unit tests/debugging
     PPU& debug_get_ppu() { return mmu.get_ppu(); }
     // Allow mmu write access for unit tests
     void debug_write_io(uint16_t addr, uint8_t value) { mmu.write_byte(addr, value); }
+    uint8_t debug_read_io(uint16_t addr) { return mmu.read_byte(addr); }
+
+    // Local two-player link cable: connect this emulator's serial port to another's.
+    // Destroying either side disconnects the cable.
+    void link(Emulator& other) { mmu.link_serial(&other.mmu); }
+    void unlink() { mmu.link_serial(nullptr); }
+    bool is_linked() const { return mmu.get_serial_peer() != nullptr; }
+    // Observe bytes sent over the serial port (test ROM output, link debugging)
+    void set_serial_callback(std::function<void(uint8_t)> callback) { mmu.set_serial_callback(std::move(callback)); }
+
+    // Frame hooks run in registration order after each emulated frame of run() or run_frame()
+    // (not owned by Emulator)
+    void add_frame_hook(FrameHook* hook);
+    void remove_frame_hook(FrameHook* hook);
 
 private:
     void handle_input(const SDL_Event& event);
+    // End-of-frame work shared by run() and run_frame(): input auto-repeat, then frame hooks
+    void end_frame();
+    // Advance auto-repeat by one frame
+    void update_input_repeat();
+    void track_health(uint16_t pc, bool executed, uint8_t opcode, int cycles);
+    void reset_health();
 
     MMU mmu;
     CPU cpu;
@@ -47,9 +254,37 @@ private:
     SDL_AudioStream* audio_stream;
     SDL_AudioDeviceID audio_device = 0;
 
+    std::string save_path; // battery RAM file next to the ROM (<rom>.sav)
+
     bool running;
     bool headless = false;
     int max_frames = 0; // 0 means run forever until window closed
+    int audio_latency_samples = 4096; // ~93 ms at 44.1 kHz
+
+    static constexpr uint32_t SAVE_STATE_VERSION = 3;
+    static constexpr double CPU_CLOCK_HZ = 4194304.0;
+    static constexpr int WINDOW_SCALE = 3; // initial window size: composited size x3
+    uint64_t total_cycles = 0;
+
+    std::optional<BorderImage> border;
+
+    // health_check() state: PC range per 70224-cycle window, consecutive tight-loop windows
+    static constexpr int HANG_FRAMES = 120;        // ~2 seconds
+    static constexpr int TIGHT_LOOP_SPAN = 16;     // bytes
+    static constexpr int FILL_OPCODE_LIMIT = 256;  // consecutive 0x00/0xFF opcodes
+    uint16_t health_pc_min = 0xFFFF;
+    uint16_t health_pc_max = 0;
+    int health_window_cycles = 0;
+    int tight_loop_frames = 0;
+    int fill_opcode_run = 0;
+    bool runaway_detected = false;
+
+    std::vector<FrameHook*> frame_hooks;
+    std::vector<std::string> reference_trace;
+
+    InputConfig input_config;
+    std::array<bool, 8> button_held{};
+    std::array<int, 8> button_held_frames{};
 };
 
 #endif // EMULATOR_H
\ No newline at end of file
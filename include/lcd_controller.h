@@ -3,6 +3,7 @@
 
 #include <cstdint>
 #include <vector>
+#include "save_state.h"
 
 class LCDController {
 public:
@@ -69,6 +70,10 @@ public:
     // Debug summary dump for LCDC ON events (#3 requirement)
     void dump_lcd_on_summary() const;
 
+    // Save state: LCD registers and line/enable tracking (debug offsets and logs are not saved)
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
+
     struct LcdOnEvent {
         uint64_t global_cycles_at_on; // global PPU cycles when LCDC bit7 rose
         uint16_t start_cycle_count;   // internal cycle_count at enable (now always 0)
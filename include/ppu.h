@@ -10,6 +10,12 @@
 
 class MMU; // Forward declaration
 
+// CGB LCD color response applied when converting 15-bit palette colors to RGB
+enum class ColorCorrection {
+    None,      // raw 5-bit -> 8-bit expansion
+    Gambatte   // washed-out channel mixing that mimics the CGB panel
+};
+
 class PPU {
 public:
     PPU();
@@ -18,14 +24,22 @@ public:
         void step(int cycles, MMU& mmu);
         void render_scanline(MMU& mmu, uint8_t shadow_scx, uint8_t shadow_scy);
     const std::array<uint32_t, 160 * 144>& get_framebuffer() const { return framebuffer; }
+    // Debug view: full 256x256 BG map (row-major, same pixel format as the framebuffer)
+    std::vector<uint32_t> render_full_background(MMU& mmu) const {
+        return background_renderer.render_full_background(mmu, lcd_controller.get_lcdc(), lcd_controller.get_bgp());
+    }
+    // Debug view: sprites the OAM scan selects for a line, highest priority first
+    std::vector<SpriteRenderer::Sprite> sprites_on_line(MMU& mmu, uint8_t line) const {
+        return sprite_renderer.select_sprites_on_line(mmu, line, lcd_controller.get_lcdc());
+    }
 
     // LCD Control Register (0xFF40)
     uint8_t get_lcdc() const { return lcd_controller.get_lcdc(); }
-    void set_lcdc(uint8_t value) { lcd_controller.set_lcdc(value); }
+    void set_lcdc(uint8_t value);
 
     // LCD Status Register (0xFF41)
     uint8_t get_stat() const { return lcd_controller.get_stat(); }
-    void set_stat(uint8_t value) { lcd_controller.set_stat(value); }
+    void set_stat(uint8_t value);
 
     // Scroll registers
     uint8_t get_scy() const { return lcd_controller.get_scy(); }
@@ -39,7 +53,7 @@ public:
 
     // LYC register (LY compare)
     uint8_t get_lyc() const { return lcd_controller.get_lyc(); }
-    void set_lyc(uint8_t value) { lcd_controller.set_lyc(value); }
+    void set_lyc(uint8_t value);
 
     // Window position
     uint8_t get_wy() const { return lcd_controller.get_wy(); }
@@ -93,6 +107,15 @@ private:
     // One-time debug print flag
     bool frame_info_printed = false;
     uint64_t global_cycles = 0; // total PPU cycles for diagnostics
+    // STAT interrupt line (OR of enabled sources); IF bit 1 is raised on its rising edge
+    bool stat_line = false;
+    // Active STAT sources (bit 3 LYC, bit 2 mode 2, bit 1 mode 1, bit 0 mode 0) for AccuracyLevel::Fast
+    uint8_t stat_sources = 0;
+    void update_lyc_coincidence();
+    uint8_t active_stat_sources() const;
+    void update_stat_line(MMU& mmu);
+    // LCDC.5 gates the window; on DMG LCDC.0 also blanks it
+    bool window_enabled(const MMU& mmu) const;
     // Removed delayed LCD enable scheduling fields; kept for reference (alignment now immediate).
 
     // --- OAM bug support ---
@@ -120,6 +143,43 @@ public:
 
     // Helper functions
     uint32_t get_color(uint8_t color_id, uint8_t palette) const;
+
+    // Built-in DMG shade sets (ARGB, lightest to darkest): the default green tint, and plain grayscale
+    static constexpr std::array<uint32_t, 4> DEFAULT_PALETTE = {0xFFFFFFFF, 0xFFCADFAA, 0xFF8EBF60, 0xFF305030};
+    static constexpr std::array<uint32_t, 4> GRAYSCALE_PALETTE = {0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000};
+    // DMG shade colors used by background, window and sprites. Default is DEFAULT_PALETTE;
+    // pass GRAYSCALE_PALETTE for gray, or any four custom colors.
+    void set_palette(const std::array<uint32_t, 4>& colors);
+    const std::array<uint32_t, 4>& get_palette() const { return shades; }
+    // Current frame re-encoded as 2bpp planar tiles (20x18 tiles, row-major, 16 bytes each).
+    // Each pixel maps to the nearest DMG shade, then back through BGP to the lowest color index that
+    // BGP turns into that shade (a shade BGP never produces is written as the shade number itself).
+    // Sprite pixels also go through BGP, so they only match their OBJ color index when OBP equals BGP.
+    std::vector<uint8_t> export_frame_2bpp() const;
+
+    // Save state: timing, LCD registers, framebuffer and CGB palette RAM (display settings are not saved)
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
+
+    // CGB color conversion (BGR555 -> 0xAARRGGBB) with optional color correction
+    void set_color_correction(ColorCorrection mode) { color_correction = mode; }
+    ColorCorrection get_color_correction() const { return color_correction; }
+    uint32_t cgb_color_to_argb(uint16_t bgr555) const;
+
+    // CGB palette RAM: BCPS/BCPD (0xFF68/0xFF69) and OCPS/OCPD (0xFF6A/0xFF6B), 8 palettes x 4 colors each.
+    // Writing the data register auto-increments the index when bit 7 of the spec register is set.
+    uint8_t read_cgb_palette_register(uint16_t address) const;
+    void write_cgb_palette_register(uint16_t address, uint8_t value);
+    uint32_t get_cgb_bg_color(uint8_t palette, uint8_t color_id) const;
+    uint32_t get_cgb_obj_color(uint8_t palette, uint8_t color_id) const;
+
+private:
+    ColorCorrection color_correction = ColorCorrection::None;
+    std::array<uint32_t, 4> shades = DEFAULT_PALETTE;
+    std::array<uint8_t, 64> cgb_bg_palette_ram;
+    std::array<uint8_t, 64> cgb_obj_palette_ram;
+    uint8_t bcps = 0;
+    uint8_t ocps = 0;
 };
 
 #endif // PPU_H
\ No newline at end of file
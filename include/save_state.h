@@ -0,0 +1,53 @@
+#ifndef SAVE_STATE_H
+#define SAVE_STATE_H
+
+#include <cstdint>
+#include <cstddef>
+#include <cstring>
+#include <vector>
+#include <type_traits>
+
+// Byte stream helpers for save states. Fields are copied as raw host-layout bytes, so a
+// state is only portable between builds with the same format version on the same platform.
+class StateWriter {
+public:
+    template <typename T>
+    void put(const T& value) {
+        static_assert(std::is_trivially_copyable_v<T>, "save state fields must be trivially copyable");
+        put_bytes(reinterpret_cast<const uint8_t*>(&value), sizeof(T));
+    }
+    void put_bytes(const uint8_t* src, size_t size) { data.insert(data.end(), src, src + size); }
+    std::vector<uint8_t> take() { return std::move(data); }
+
+private:
+    std::vector<uint8_t> data;
+};
+
+// Reads fields back in the order they were written. Once a read runs past the end,
+// good() stays false and further reads leave their targets untouched.
+class StateReader {
+public:
+    StateReader(const uint8_t* data, size_t size) : data(data), size(size) {}
+
+    template <typename T>
+    void get(T& value) {
+        static_assert(std::is_trivially_copyable_v<T>, "save state fields must be trivially copyable");
+        get_bytes(reinterpret_cast<uint8_t*>(&value), sizeof(T));
+    }
+    void get_bytes(uint8_t* dst, size_t count) {
+        if (!ok || count > size - pos) { ok = false; return; }
+        std::memcpy(dst, data + pos, count);
+        pos += count;
+    }
+    void fail() { ok = false; }
+    bool good() const { return ok; }
+    bool at_end() const { return pos == size; }
+
+private:
+    const uint8_t* data;
+    size_t size;
+    size_t pos = 0;
+    bool ok = true;
+};
+
+#endif // SAVE_STATE_H
@@ -31,14 +31,22 @@ public:
                        std::array<uint32_t, 160 * 144>& framebuffer,
                        const std::array<uint8_t, 160 * 144>& bgwin_pixel_ids);
 
+    // OAM scan for one line (10-sprite cap, 8x8/8x16 height) in drawing priority order, highest first
+    std::vector<Sprite> select_sprites_on_line(MMU& mmu, uint8_t ly, uint8_t lcdc) const;
+
+    // ARGB colors for DMG shades 0-3 (set by PPU::set_palette; the PPU constructor installs its default)
+    void set_shades(const std::array<uint32_t, 4>& colors) { shades = colors; }
+
 private:
+    std::array<uint32_t, 4> shades{};
+
     // Helper functions
     std::vector<Sprite> collect_sprites_on_line(MMU& mmu, uint8_t ly, uint8_t lcdc) const;
     void render_sprite_pixels(MMU& mmu, const std::vector<Sprite>& sprites_on_line,
                              uint8_t ly, uint8_t lcdc, uint8_t obp0, uint8_t obp1,
                              std::array<uint32_t, 160 * 144>& framebuffer,
                              const std::array<uint8_t, 160 * 144>& bgwin_pixel_ids);
-    uint8_t get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y) const;
+    uint8_t get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y, uint8_t bank = 0) const;
     uint32_t get_color(uint8_t color_id, uint8_t palette) const;
 };
 
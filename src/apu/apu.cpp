@@ -1,6 +1,7 @@
 #include "apu.h"
 #include <iostream>
 #include <cmath>
+#include <cstring>
 
 
 const APU::RegisterDescriptor APU::reg_table[0x17] = {
@@ -36,7 +37,7 @@ const APU::RegisterDescriptor APU::reg_table[0x17] = {
 };
 
 
-APU::APU() : frame_counter(0), frame_step(0), sample_timer(0.0), cycles_per_sample(CPU_CLOCK / SAMPLE_RATE) {
+APU::APU() : frame_counter(0), frame_step(0), sample_timer(0.0), cycles_per_sample(static_cast<double>(CPU_CLOCK) / SAMPLE_RATE) {
     reset();
 }
 
@@ -75,17 +76,13 @@ void APU::reset() {
 }
 
 void APU::step(int cycles) {
-    sample_timer += cycles;
-    while (sample_timer >= cycles_per_sample) {
-        sample_timer -= cycles_per_sample;
-        mix_and_push_sample();
-    }
-
-    frame_counter += cycles;
+    if (!frame_sequencer_from_div) {
+        frame_counter += cycles;
 
-    while (frame_counter >= FRAME_SEQUENCER_PERIOD) {
-        frame_counter -= FRAME_SEQUENCER_PERIOD;
-        update_frame_sequencer();
+        while (frame_counter >= FRAME_SEQUENCER_PERIOD) {
+            frame_counter -= FRAME_SEQUENCER_PERIOD;
+            update_frame_sequencer();
+        }
     }
 
     update_pulse_timer(ch1, cycles);
@@ -98,7 +95,7 @@ void APU::step(int cycles) {
         static const uint16_t div_table[8] = {8,16,32,48,64,80,96,112};
         uint16_t dividing_ratio = div_table[div_code];
         uint8_t shift = (ch4.polynomial >> 4) & 0x0F;
-        uint16_t period = dividing_ratio << (shift + 1);
+        uint32_t period = static_cast<uint32_t>(dividing_ratio) << shift;  // divisor << shift
         while (ch4.timer >= period) {
             ch4.timer -= period;
             uint8_t bit = (ch4.lfsr & 0x01) ^ ((ch4.lfsr >> 1) & 0x01);
@@ -149,11 +146,11 @@ void APU::update_wave_timer(WaveChannel& ch, int cycles) {
 
 uint8_t APU::read_register(uint16_t address) const {
     if (address >= 0xFF30 && address <= 0xFF3F) {
-        return debug_read(address, read_wave_ram(address));
+        return read_wave_ram(address);
     }
     
     if (address < 0xFF10 || address > 0xFF26) {
-        return debug_read(address, 0xFF);  // Out of range
+        return 0xFF;  // Out of range
     }
     
     const uint8_t index = address - 0xFF10;
@@ -162,9 +159,9 @@ uint8_t APU::read_register(uint16_t address) const {
     if (!apu_powered()) {
         if (address == 0xFF26) {
             const_cast<APU*>(this)->flush_for_nr52_read();
-            return debug_read(address, (uint8_t)(0x70 | get_channel_status()));
+            return (uint8_t)(0x70 | get_channel_status());
         }
-        return debug_read(address, desc.default_read);
+        return desc.default_read;
     }
 
     uint8_t value = regs[index];
@@ -176,7 +173,7 @@ uint8_t APU::read_register(uint16_t address) const {
 
     value = (value & desc.read_mask) | (desc.default_read & ~desc.read_mask);
 
-    return debug_read(address, value);
+    return value;
 }
 
 
@@ -258,20 +255,23 @@ void APU::update_dac_state(uint16_t address, uint8_t value) {
 }
 
 
+// Wave RAM while CH3 is playing (DMG): the CPU only reaches the byte CH3 is fetching, and only
+// on the same cycle as that fetch; any other access reads 0xFF / is dropped. The APU is stepped
+// per instruction, so that window is never hit here: reads always return 0xFF and writes are
+// ignored while CH3 is on. "On" is the NR52 status bit, so turning the DAC off (NR30) or an
+// expired length counter unlocks wave RAM; the new table plays on the next trigger.
 uint8_t APU::read_wave_ram(uint16_t address) const {
-    if (ch3_state.enabled) {
+    if (get_channel_status() & 0x04) {
         return 0xFF;
     }
     return wave_ram[address - 0xFF30];
 }
 
 void APU::write_wave_ram(uint16_t address, uint8_t value) {
-    if (apu_powered() && ch3_state.enabled) {
-        debug_log("WR", address, value);  // Ignore writes when CH3 is enabled
-        return;
+    if (apu_powered() && (get_channel_status() & 0x04)) {
+        return;  // Ignore writes when CH3 is enabled
     }
     wave_ram[address - 0xFF30] = value;
-    debug_log("WR", address, value);
 }
 
 void APU::trigger_channel(int channel_num) {
@@ -354,15 +354,23 @@ void APU::initialize_sweep(int channel_num) {
 void APU::initialize_envelope(int channel_num) {
     // Initialize envelope for applicable channels
     // Envelope: Volume from NRx2 bits 4-7, period from bits 0-2
+    // Direction from bit 3 (1 = increase)
     if (channel_num == 1) {
         ch1.envelope_volume = (regs[0x02] >> 4);  // NR12 initial volume
         ch1.envelope_counter = (regs[0x02] & 7);  // NR12 period
+        ch1.envelope_period = (regs[0x02] & 7);
+        ch1.envelope_increase = (regs[0x02] & 0x08) != 0;
     } else if (channel_num == 2) {
         ch2.envelope_volume = (regs[0x07] >> 4);  // NR22 initial volume
         ch2.envelope_counter = (regs[0x07] & 7);  // NR22 period
+        ch2.envelope_period = (regs[0x07] & 7);
+        ch2.envelope_increase = (regs[0x07] & 0x08) != 0;
     } else if (channel_num == 4) {
         ch4.envelope_volume = (regs[0x11] >> 4);  // NR42 initial volume
         ch4.envelope_counter = (regs[0x11] & 7);  // NR42 period
+        ch4.envelope_period = (regs[0x11] & 7);
+        ch4.envelope_increase = (regs[0x11] & 0x08) != 0;
+        ch4.polynomial = regs[0x12];               // NR43
     }
 }
 
@@ -465,6 +473,9 @@ void APU::handle_length_trigger(uint16_t address, uint8_t value, uint8_t old_reg
             ch.frequency = ((regs[reg_offset] & 7) << 8) | regs[reg_offset - 1];
             if (channel_num == 1) ch1.sweep_frequency = ch1.frequency;
         }
+    } else if (channel_num == 3 && state->enabled) {
+        // NR34 觸發時也要載入頻率，否則 CH3 會以觸發前的舊頻率（初始為 0）播放
+        ch3.frequency = ((regs[0x0E] & 7) << 8) | regs[0x0D];
     }
 }
 
@@ -536,7 +547,6 @@ void APU::write_register(uint16_t address, uint8_t value) {
     bool apu_on = apu_powered();
     
     if (!apu_on && !desc.writable_when_off && address != 0xFF26) {
-        debug_log("WR", address, value);
         return;  // 完全忽略
     }
 
@@ -557,7 +567,6 @@ void APU::write_register(uint16_t address, uint8_t value) {
         }
         
         regs[reg_idx] = (value & 0x80) | get_channel_status();
-        debug_log("WR", address, value);
         return;
     }
     
@@ -576,7 +585,7 @@ void APU::write_register(uint16_t address, uint8_t value) {
         case NR33: handle_frequency_low(3, value); break;
         case NR41: handle_noise_length(value); break;
         case NR42: handle_envelope(4, value); break;
-        case NR43: /* Noise polynomial, no special handling */ break;
+        case NR43: /* Noise polynomial, applied in handle_side_effects */ break;
         case NR14: handle_length_trigger(address, value, old_reg, 1); break;
         case NR24: handle_length_trigger(address, value, old_reg, 2); break;
         case NR34: handle_length_trigger(address, value, old_reg, 3); break;
@@ -584,8 +593,6 @@ void APU::write_register(uint16_t address, uint8_t value) {
     }
 
     handle_side_effects(address, value, old_reg);
-    
-    debug_log("WR", address, value);
 }
 
 void APU::handle_side_effects(uint16_t address, uint8_t value, uint8_t old_reg) {
@@ -602,6 +609,9 @@ void APU::handle_side_effects(uint16_t address, uint8_t value, uint8_t old_reg)
         case NR41:  // NR41
             handle_nr41_side_effects(value);
             break;
+        case NR43:  // NR43: clock shift / width / divisor, takes effect immediately
+            ch4.polynomial = regs[0x12];
+            break;
     }
     update_dac_state(address, value);  // 更新 dac_on
 }
@@ -626,6 +636,21 @@ void APU::handle_nr41_side_effects(uint8_t value) {
 
 
 
+void APU::apply_audio_config(const AudioConfig& config) {
+    audio_config = config;
+    if (audio_config.sample_rate <= 0) audio_config.sample_rate = SAMPLE_RATE;
+    cycles_per_sample = static_cast<double>(CPU_CLOCK) / audio_config.sample_rate;
+    if (audio_config.high_pass_cutoff > 0.0f) {
+        float rc = 1.0f / (2.0f * 3.14159265f * audio_config.high_pass_cutoff);
+        float dt = 1.0f / static_cast<float>(audio_config.sample_rate);
+        high_pass_alpha = rc / (rc + dt);
+    } else {
+        high_pass_alpha = 1.0f;
+    }
+    high_pass_prev_in = {0.0f, 0.0f};
+    high_pass_prev_out = {0.0f, 0.0f};
+}
+
 void APU::get_audio_samples(float* buffer, int length) {
     bool powered = apu_powered();
     if (!powered) {
@@ -634,7 +659,22 @@ void APU::get_audio_samples(float* buffer, int length) {
     }
 
     for (int i = 0; i < length; i++) {
-        if (!audio_fifo.empty()) {
+        if (audio_fifo.size() >= 2) {
+            float left = audio_fifo.front();
+            audio_fifo.pop_front();
+            float right = audio_fifo.front();
+            audio_fifo.pop_front();
+            buffer[i] = (left + right) * 0.5f;
+        } else {
+            buffer[i] = 0.0f;
+        }
+    }
+}
+
+void APU::get_audio_samples_stereo(float* buffer, int frames) {
+    bool powered = apu_powered();
+    for (int i = 0; i < frames * 2; i++) {
+        if (powered && !audio_fifo.empty()) {
             buffer[i] = audio_fifo.front();
             audio_fifo.pop_front();
         } else {
@@ -643,14 +683,72 @@ void APU::get_audio_samples(float* buffer, int length) {
     }
 }
 
-void APU::update_frame_sequencer() {
-    frame_step = (frame_step + 1) & 7;
+void APU::save_state(StateWriter& w) const {
+    w.put(regs);
+    w.put(wave_ram);
+    w.put(ch1);
+    w.put(ch2);
+    w.put(ch3);
+    w.put(ch4);
+    w.put(ch1_state);
+    w.put(ch2_state);
+    w.put(ch3_state);
+    w.put(ch4_state);
+    w.put(nr50);
+    w.put(nr51);
+    w.put(nr52);
+    w.put(frame_counter);
+    w.put(frame_step);
+    w.put(sample_timer);
+    w.put(apu_was_off);
+}
+
+void APU::load_state(StateReader& r) {
+    r.get(regs);
+    r.get(wave_ram);
+    r.get(ch1);
+    r.get(ch2);
+    r.get(ch3);
+    r.get(ch4);
+    r.get(ch1_state);
+    r.get(ch2_state);
+    r.get(ch3_state);
+    r.get(ch4_state);
+    r.get(nr50);
+    r.get(nr51);
+    r.get(nr52);
+    r.get(frame_counter);
+    r.get(frame_step);
+    r.get(sample_timer);
+    r.get(apu_was_off);
+    audio_fifo.clear();
+}
 
-    if (GB_APU_DEBUG) {
-        bool len_tick = ((frame_step & 1) == 0);
-        if (!this->debug_len_only || len_tick) {
-        }
+std::array<float, 4> APU::channel_frequencies() const {
+    std::array<float, 4> freqs{0.0f, 0.0f, 0.0f, 0.0f};
+    uint8_t status = apu_powered() ? get_channel_status() : 0;
+
+    // Period comes from NRx3/NRx4 (sweep writes its result back to NR13/NR14)
+    auto period = [this](uint8_t lo_index) {
+        return static_cast<uint16_t>(((regs[lo_index + 1] & 0x07) << 8) | regs[lo_index]);
+    };
+    if (status & 0x01) freqs[0] = 131072.0f / (2048 - period(0x03));
+    if (status & 0x02) freqs[1] = 131072.0f / (2048 - period(0x08));
+    if (status & 0x04) freqs[2] = 65536.0f / (2048 - period(0x0D));
+    if (status & 0x08) {
+        uint8_t nr43 = regs[0x12];
+        float divisor = (nr43 & 0x07) ? static_cast<float>(nr43 & 0x07) : 0.5f;
+        freqs[3] = 262144.0f / (divisor * static_cast<float>(1 << ((nr43 >> 4) & 0x0F)));
     }
+    return freqs;
+}
+
+void APU::clock_frame_sequencer() {
+    update_frame_sequencer();
+}
+
+void APU::update_frame_sequencer() {
+    frame_step = (frame_step + 1) & 7;
 
     if ((frame_step & 1) == 0) {
         update_length(1);
@@ -730,8 +828,6 @@ void APU::update_envelope(PulseChannel& ch) {
         } else {
             if (ch.envelope_volume > 0) ch.envelope_volume--;
         }
-        if (GB_APU_DEBUG && !this->debug_len_only) {
-        }
     } else {
         ch.envelope_counter--;
     }
@@ -747,8 +843,6 @@ void APU::update_envelope(NoiseChannel& ch) {
         } else {
             if (ch.envelope_volume > 0) ch.envelope_volume--;
         }
-        if (GB_APU_DEBUG && !this->debug_len_only) {
-        }
     } else {
         ch.envelope_counter--;
     }
@@ -800,15 +894,12 @@ void APU::update_length(int channel_num) {
         } else if (is_noise) {
             state->dac_on = (ch4.envelope & 0xF0) != 0;
         }
-
-        if (GB_APU_DEBUG) {
-        }
     }
 }
 
 float APU::generate_pulse_sample(const PulseChannel& ch) const {
     if (!ch.enabled) return 0.0f;
-    uint8_t duty = (ch.length >> 6) & 3; // NRx1 duty
+    uint8_t duty = ch.duty & 3; // NRx1 duty, decoded on write
     uint8_t waveform = get_duty_waveform(duty, ch.position);
     float sample = waveform ? 1.0f : -1.0f;
     sample *= ch.envelope_volume / 15.0f;
@@ -845,16 +936,31 @@ uint8_t APU::get_duty_waveform(uint8_t duty, uint8_t position) const {
 }
 
 void APU::mix_and_push_sample() {
-    float sample = 0.0f;
+    float side[2] = {0.0f, 0.0f};  // [0] = left (SO2), [1] = right (SO1)
     if (apu_powered()) {
-        sample += generate_pulse_sample(ch1);
-        sample += generate_pulse_sample(ch2);
-        sample += generate_wave_sample(ch3);
-        sample += generate_noise_sample(ch4);
-        uint8_t nr50 = regs[0x14]; // NR50
-        uint8_t left_vol = nr50 & 7;
-        uint8_t right_vol = (nr50 >> 4) & 7;
-        sample *= (left_vol + right_vol) / 14.0f;
-    }
-    audio_fifo.push_back(sample);
+        float ch[4] = {0.0f, 0.0f, 0.0f, 0.0f};
+        if (audio_config.channel_enabled[0]) ch[0] = generate_pulse_sample(ch1);
+        if (audio_config.channel_enabled[1]) ch[1] = generate_pulse_sample(ch2);
+        if (audio_config.channel_enabled[2]) ch[2] = generate_wave_sample(ch3);
+        if (audio_config.channel_enabled[3]) ch[3] = generate_noise_sample(ch4);
+        uint8_t nr51 = regs[0x15]; // NR51: bit 4-7 -> left, bit 0-3 -> right
+        for (int i = 0; i < 4; i++) {
+            if (nr51 & (0x10 << i)) side[0] += ch[i];
+            if (nr51 & (0x01 << i)) side[1] += ch[i];
+        }
+        uint8_t nr50 = regs[0x14]; // NR50: bit 4-6 left volume, bit 0-2 right volume
+        side[0] *= (((nr50 >> 4) & 7) + 1) / 8.0f;
+        side[1] *= ((nr50 & 7) + 1) / 8.0f;
+    }
+    for (int s = 0; s < 2; s++) {
+        float sample = side[s] * audio_config.master_gain;
+        if (audio_config.high_pass_cutoff > 0.0f) {
+            // 一階高通：y[n] = a * (y[n-1] + x[n] - x[n-1])
+            float out = high_pass_alpha * (high_pass_prev_out[s] + sample - high_pass_prev_in[s]);
+            high_pass_prev_in[s] = sample;
+            high_pass_prev_out[s] = out;
+            sample = out;
+        }
+        audio_fifo.push_back(sample);
+    }
 }
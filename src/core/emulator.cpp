@@ -7,6 +7,8 @@
 #include <windows.h>
 #endif
 #include <fstream>
+#include <algorithm>
+#include <cstring>
 #ifndef EMU_FRAME_DEBUG
 #define EMU_FRAME_DEBUG 0
 #endif
@@ -22,9 +24,10 @@ bool Emulator::initialize() {
     // Initialize SDL3 - skip global init and let individual functions initialize as needed
 
     if (!headless) {
-        // Create window (GameBoy resolution: 160x144, scaled up)
-        window = SDL_CreateWindow("GameBoy Emulator", 160 * 3, 144 * 3, SDL_WINDOW_RESIZABLE);
+        // Create window (GameBoy resolution: 160x144, or the border size if one is set, scaled up)
+        window = SDL_CreateWindow("GameBoy Emulator", composited_width() * WINDOW_SCALE, composited_height() * WINDOW_SCALE, SDL_WINDOW_RESIZABLE);
         if (!window) {
+            std::cerr << "[Emulator] SDL_CreateWindow failed: " << SDL_GetError() << std::endl;
             return false;
         }
         SDL_ShowWindow(window);
@@ -32,25 +35,33 @@ bool Emulator::initialize() {
         // Create renderer
         renderer = SDL_CreateRenderer(window, nullptr);
         if (!renderer) {
+            std::cerr << "[Emulator] SDL_CreateRenderer failed: " << SDL_GetError() << std::endl;
             SDL_DestroyWindow(window);
+            window = nullptr;
             return false;
         }
 
         // Create texture for rendering
-        texture = SDL_CreateTexture(renderer, SDL_PIXELFORMAT_RGBA8888, SDL_TEXTUREACCESS_STREAMING, 160, 144);
+        texture = SDL_CreateTexture(renderer, SDL_PIXELFORMAT_RGBA8888, SDL_TEXTUREACCESS_STREAMING,
+                                    composited_width(), composited_height());
         if (!texture) {
+            std::cerr << "[Emulator] SDL_CreateTexture failed: " << SDL_GetError() << std::endl;
             SDL_DestroyRenderer(renderer);
             SDL_DestroyWindow(window);
+            renderer = nullptr;
+            window = nullptr;
             return false;
         }
     }
 
     // Audio initialization (SDL3: 直接開啟綁定裝置的 AudioStream，推資料即可播放)
     // 初始化音訊子系統
-    bool audio_init_success = (SDL_Init(SDL_INIT_AUDIO) == 0);
+    bool audio_init_success = SDL_Init(SDL_INIT_AUDIO);
     if (audio_init_success) {
+        // 裝置緩衝區大小跟著延遲預算走（需在開啟裝置前設定）
+        SDL_SetHint(SDL_HINT_AUDIO_DEVICE_SAMPLE_FRAMES, std::to_string(audio_buffer_frames()).c_str());
         SDL_AudioSpec want{};
-        want.freq = 44100;
+        want.freq = mmu.get_apu().get_audio_config().sample_rate;
         want.format = SDL_AUDIO_S16LE;
         want.channels = 2;
         audio_stream = SDL_OpenAudioDeviceStream(SDL_AUDIO_DEVICE_DEFAULT_PLAYBACK, &want, nullptr, nullptr);
@@ -58,17 +69,20 @@ bool Emulator::initialize() {
             // 檢查實際獲得的音訊規格
             SDL_AudioSpec obtained{};
             SDL_GetAudioStreamFormat(audio_stream, &obtained, nullptr);
+            SDL_ResumeAudioStreamDevice(audio_stream); // SDL3 opens device streams paused
         }
     }
 
     // Initialize CPU and MMU
     cpu.reset();
+    total_cycles = 0;
+    reset_health();
 
     running = true;
     return true;
 }
 
-bool Emulator::load_rom(const std::string& rom_path) {
+bool Emulator::load_rom(const std::string& rom_path, bool strict_header) {
     std::ifstream file(rom_path, std::ios::binary | std::ios::ate);
     if (!file.is_open()) {
         return false;
@@ -82,9 +96,32 @@ bool Emulator::load_rom(const std::string& rom_path) {
         return false;
     }
 
+    // 標頭檢查：開機 ROM 會因 logo/checksum 不符而停住，這裡預設只警告
+    bool logo_ok = MMU::header_logo_valid(rom_data);
+    bool checksum_ok = MMU::header_checksum_valid(rom_data);
+    if (!logo_ok || !checksum_ok) {
+        std::cerr << "[ROM] " << (strict_header ? "Error" : "Warning") << ": "
+                  << (!logo_ok ? "Nintendo logo mismatch" : "")
+                  << (!logo_ok && !checksum_ok ? ", " : "")
+                  << (!checksum_ok ? "header checksum mismatch" : "")
+                  << " in " << rom_path << std::endl;
+        if (strict_header) return false;
+    }
+
+    // 換卡前先存下目前卡匣的電池 RAM，否則會被新卡覆蓋而遺失進度
+    if (!save_path.empty()) { mmu.save_ram(save_path); }
     if (!mmu.load_rom(rom_data)) {
         return false;
     }
+    total_cycles = 0;
+    reset_health();
+
+    // Battery-backed save: <rom name>.sav in the same directory
+    size_t dot = rom_path.find_last_of('.');
+    size_t sep = rom_path.find_last_of("/\\");
+    save_path = (dot != std::string::npos && (sep == std::string::npos || dot > sep)) ? rom_path.substr(0, dot) : rom_path;
+    save_path += ".sav";
+    mmu.load_ram(save_path);
 
     // Enable quick timing mode for timing test ROMs (Route A)
     if (rom_path.find("read_timing") != std::string::npos || 
@@ -96,9 +133,15 @@ bool Emulator::load_rom(const std::string& rom_path) {
     return true;
 }
 
-void Emulator::run() {
+bool Emulator::run() {
 #ifndef EMU_FRAME_DEBUG
 #endif
+    // 視窗模式必須先 initialize() 建立視窗；否則明確回報而不是默默不執行
+    if (!headless && !window) {
+        std::cerr << "[Emulator] run() has no window to draw to: call initialize() first, "
+                     "or set_headless(true) to run without video" << std::endl;
+        return false;
+    }
     int frame_count = 0;
     while (running) {
         frame_count++;
@@ -110,21 +153,26 @@ void Emulator::run() {
                     running = false;
                 }
             }
-            const auto& framebuffer = mmu.get_ppu().get_framebuffer();
-            SDL_UpdateTexture(texture, nullptr, framebuffer.data(), 160 * sizeof(uint32_t));
+            if (border) {
+                std::vector<uint32_t> frame = composited_frame();
+                SDL_UpdateTexture(texture, nullptr, frame.data(), border->width * static_cast<int>(sizeof(uint32_t)));
+            } else {
+                const auto& framebuffer = mmu.get_ppu().get_framebuffer();
+                SDL_UpdateTexture(texture, nullptr, framebuffer.data(), 160 * sizeof(uint32_t));
+            }
             SDL_SetRenderDrawColor(renderer, 0, 0, 0, 255);
             SDL_RenderClear(renderer);
             SDL_RenderTexture(renderer, texture, nullptr, nullptr);
             SDL_RenderPresent(renderer);
         }
 
-        int total_cycles = 0;
+        int frame_cycles = 0;
         const int target_cycles = 70224; // cycles per frame
-        const int audio_samples_per_frame = 735; // 44100 / 60
+        const int audio_samples_per_frame = mmu.get_apu().get_audio_config().sample_rate / 60; // 735 at 44.1 kHz
 
         if (audio_stream) {
-            std::vector<float> audio_buffer_float(audio_samples_per_frame); // mono
-            mmu.get_apu().get_audio_samples(audio_buffer_float.data(), static_cast<int>(audio_buffer_float.size()));
+            std::vector<float> audio_buffer_float(audio_samples_per_frame * 2); // interleaved L/R
+            mmu.get_apu().get_audio_samples_stereo(audio_buffer_float.data(), audio_samples_per_frame);
             // 檢查是否有非零資料
             bool has_non_zero = false;
             for (float sample : audio_buffer_float) {
@@ -133,21 +181,27 @@ void Emulator::run() {
             // 轉換為 S16LE 立體聲
             std::vector<int16_t> audio_buffer(audio_samples_per_frame * 2);
             for (size_t i = 0; i < audio_buffer_float.size(); ++i) {
-                int16_t sample = static_cast<int16_t>(audio_buffer_float[i] * 32767.0f);
-                audio_buffer[i * 2] = sample;     // left
-                audio_buffer[i * 2 + 1] = sample; // right
+                audio_buffer[i] = static_cast<int16_t>(audio_buffer_float[i] * 32767.0f);
+            }
+            // 限制佇列深度：只排入預算內放得下的樣本，多出的尾端捨棄，避免延遲無限累積
+            int queued_samples = SDL_GetAudioStreamQueued(audio_stream) / static_cast<int>(2 * sizeof(int16_t));
+            int room = std::min(audio_latency_samples - queued_samples, audio_samples_per_frame);
+            if (room > 0) {
+                SDL_PutAudioStreamData(audio_stream, audio_buffer.data(), room * static_cast<int>(2 * sizeof(int16_t)));
             }
-            SDL_PutAudioStreamData(audio_stream, audio_buffer.data(), static_cast<int>(audio_buffer.size() * sizeof(int16_t)));
         }
 
-        int halt_cycles = 0;
-        while (total_cycles < target_cycles) {
-            int cycles = cpu.step();
-            if (cycles == 0) { cycles = 4; halt_cycles++; }
-            total_cycles += cycles;
-            mmu.get_ppu().step(cycles, mmu);
-            mmu.get_apu().step(cycles);
+        while (frame_cycles < target_cycles) {
+            frame_cycles += step();
+            if (cpu.stop_reason() != CPU::StopReason::None) break;
         }
+        if (cpu.stop_reason() != CPU::StopReason::None) {
+            // 中斷點/監看點：離開執行迴圈，交由呼叫端檢查狀態
+            running = false;
+            break;
+        }
+
+        end_frame();
 
         if (max_frames > 0 && frame_count >= max_frames) {
             const char* out = "frame_end.ppm";
@@ -157,9 +211,202 @@ void Emulator::run() {
         }
     }
     mmu.get_ppu().dump_lcd_on_summary();
+    return true;
+}
+
+bool Emulator::set_border(std::optional<BorderImage> image) {
+    if (image) {
+        if (image->width <= 0 || image->height <= 0 ||
+            image->pixels.size() != static_cast<size_t>(image->width) * static_cast<size_t>(image->height)) return false;
+        if (image->game_x < 0 || image->game_y < 0 ||
+            image->game_x + 160 > image->width || image->game_y + 144 > image->height) return false;
+    }
+    border = std::move(image);
+    if (renderer) {
+        // 貼圖尺寸跟著外框改變
+        if (texture) SDL_DestroyTexture(texture);
+        texture = SDL_CreateTexture(renderer, SDL_PIXELFORMAT_RGBA8888, SDL_TEXTUREACCESS_STREAMING,
+                                    composited_width(), composited_height());
+    }
+    if (window) {
+        // 視窗跟著外框比例（SGB 8:7 而非 10:9），避免拉伸
+        SDL_SetWindowSize(window, composited_width() * WINDOW_SCALE, composited_height() * WINDOW_SCALE);
+    }
+    return true;
+}
+
+std::vector<uint32_t> Emulator::composited_frame() const {
+    const auto& framebuffer = mmu.get_ppu().get_framebuffer();
+    if (!border) return std::vector<uint32_t>(framebuffer.begin(), framebuffer.end());
+    std::vector<uint32_t> frame = border->pixels;
+    for (int y = 0; y < 144; ++y) {
+        std::copy_n(framebuffer.begin() + y * 160, 160,
+                    frame.begin() + (border->game_y + y) * border->width + border->game_x);
+    }
+    return frame;
+}
+
+int Emulator::audio_buffer_frames() const {
+    int frames = 64;
+    while (frames * 2 <= audio_latency_samples / 2) frames *= 2;
+    return frames;
+}
+
+void Emulator::apply_audio_config(const AudioConfig& config) {
+    mmu.get_apu().apply_audio_config(config);
+    if (audio_stream) {
+        // 輸出取樣率改變時同步調整 SDL stream 的輸入格式，由 SDL 負責重新取樣
+        SDL_AudioSpec spec{};
+        spec.freq = mmu.get_apu().get_audio_config().sample_rate;
+        spec.format = SDL_AUDIO_S16LE;
+        spec.channels = 2;
+        SDL_SetAudioStreamFormat(audio_stream, &spec, nullptr);
+    }
+}
+
+int Emulator::step() {
+    uint16_t pc = cpu.PC;
+    bool executing = !cpu.halted;
+    uint8_t opcode = executing ? mmu.peek_byte(pc) : 0;
+    int cycles = cpu.step();
+    if (cpu.stop_reason() == CPU::StopReason::Breakpoint) return 0; // nothing executed
+    if (cycles == 0) cycles = 4; // halted: keep PPU/APU moving
+    // CGB double speed: the CPU (and timer) run at 8 MHz, PPU/APU still see normal-speed cycles
+    if (mmu.is_double_speed()) cycles /= 2;
+    mmu.get_ppu().step(cycles, mmu);
+    mmu.get_apu().step(cycles);
+    total_cycles += static_cast<uint64_t>(cycles);
+    track_health(pc, executing, opcode, cycles);
+    return cycles;
+}
+
+void Emulator::track_health(uint16_t pc, bool executed, uint8_t opcode, int cycles) {
+    if (executed) {
+        // 空白記憶體（全 0x00 或 0xFF）連續被當成指令執行，或 PC 跑進 OAM/未使用區/I/O
+        fill_opcode_run = (opcode == 0x00 || opcode == 0xFF) ? fill_opcode_run + 1 : 0;
+        if (fill_opcode_run >= FILL_OPCODE_LIMIT || (pc >= 0xFE00 && pc < 0xFF80)) runaway_detected = true;
+    }
+    health_pc_min = std::min(health_pc_min, pc);
+    health_pc_max = std::max(health_pc_max, pc);
+    health_window_cycles += cycles;
+    if (health_window_cycles >= 70224) {
+        // 一整幀 PC 都困在幾個位元組內（中斷處理常式也沒跑）視為可能卡死
+        health_window_cycles -= 70224;
+        bool tight = health_pc_max - health_pc_min < TIGHT_LOOP_SPAN;
+        tight_loop_frames = tight ? tight_loop_frames + 1 : 0;
+        health_pc_min = 0xFFFF;
+        health_pc_max = 0;
+    }
+}
+
+void Emulator::reset_health() {
+    health_pc_min = 0xFFFF;
+    health_pc_max = 0;
+    health_window_cycles = 0;
+    tight_loop_frames = 0;
+    fill_opcode_run = 0;
+    runaway_detected = false;
+}
+
+PeekedInstruction Emulator::peek_instruction() {
+    PeekedInstruction info;
+    info.pc = cpu.PC;
+    info.opcode = mmu.peek_byte(cpu.PC);
+    auto [text, length] = CPU::disassemble(mmu, cpu.PC);
+    info.mnemonic = std::move(text);
+    info.length = length;
+    return info;
+}
+
+EmulationHealth Emulator::health_check() const {
+    if (runaway_detected) return EmulationHealth::RunawayExecution;
+    if (cpu.SP < 0x8000 || (cpu.SP >= 0xFE00 && cpu.SP < 0xFF80)) return EmulationHealth::StackOutOfRange;
+    if (tight_loop_frames >= HANG_FRAMES) return EmulationHealth::SuspectedHang;
+    return EmulationHealth::Ok;
+}
+
+const std::array<uint32_t, 160 * 144>& Emulator::run_frame() {
+    const int cycles_per_frame = 70224;
+    PPU& ppu = mmu.get_ppu();
+    uint8_t prev_ly = ppu.get_ly();
+    int elapsed = 0;
+    while (true) {
+        elapsed += step();
+        if (cpu.stop_reason() != CPU::StopReason::None) break;
+        uint8_t ly = ppu.get_ly();
+        if (ly == 144 && prev_ly != 144) break;
+        // LCD 關閉時 LY 不會前進，以一幀的週期數為界
+        if (!(ppu.get_lcdc() & 0x80) && elapsed >= cycles_per_frame) break;
+        prev_ly = ly;
+    }
+    // 被中斷點/監看點打斷的不算完整一幀
+    if (cpu.stop_reason() == CPU::StopReason::None) end_frame();
+    return ppu.get_framebuffer();
+}
+
+void Emulator::end_frame() {
+    update_input_repeat();
+    for (FrameHook* hook : frame_hooks) {
+        hook->on_frame(*this);
+    }
+}
+
+int64_t Emulator::run_until_memory(uint16_t addr, uint8_t value, uint64_t max_cycles) {
+    uint64_t elapsed = 0;
+    while (mmu.read_byte(addr) != value) {
+        if (elapsed >= max_cycles) return -1;
+        elapsed += static_cast<uint64_t>(step());
+        if (cpu.stop_reason() != CPU::StopReason::None) return -1;
+    }
+    return static_cast<int64_t>(elapsed);
+}
+
+bool Emulator::load_reference_trace(const std::string& path) {
+    std::ifstream file(path);
+    if (!file) return false;
+    reference_trace.clear();
+    std::string line;
+    while (std::getline(file, line)) {
+        if (!line.empty() && line.back() == '\r') line.pop_back();
+        if (!line.empty()) reference_trace.push_back(line);
+    }
+    return true;
+}
+
+TraceDivergence Emulator::run_against_reference() {
+    TraceDivergence result;
+    size_t index = 0;
+    int halted_cycles = 0;
+    while (index < reference_trace.size()) {
+        // HALT 期間沒有執行指令，參考 trace 也不會有對應行；超過一幀仍未喚醒視為分歧
+        if (cpu.halted) {
+            halted_cycles += step();
+            if (halted_cycles < 70224) continue;
+            result.diverged = true;
+            result.line = index + 1;
+            result.expected = reference_trace[index];
+            result.actual = "HALT (no interrupt within one frame)";
+            if (index > 0) result.previous = reference_trace[index - 1];
+            return result;
+        }
+        halted_cycles = 0;
+        std::string actual = cpu.trace_line();
+        if (actual != reference_trace[index]) {
+            result.diverged = true;
+            result.line = index + 1;
+            result.expected = reference_trace[index];
+            result.actual = actual;
+            if (index > 0) result.previous = reference_trace[index - 1];
+            return result;
+        }
+        step();
+        ++index;
+    }
+    return result;
 }
 
 void Emulator::shutdown() {
+    if (!save_path.empty()) { mmu.save_ram(save_path); }
     if (audio_stream) { SDL_DestroyAudioStream(audio_stream); audio_stream = nullptr; }
     if (audio_device) { SDL_CloseAudioDevice(audio_device); audio_device = 0; }
     if (texture) { SDL_DestroyTexture(texture); texture = nullptr; }
@@ -184,16 +431,89 @@ void Emulator::handle_input(const SDL_Event& event) {
             case SDL_SCANCODE_KP_ENTER: bit = 7; break; // Start
         }
         if (bit != -1) {
-            mmu.set_joypad_bit(bit, pressed);
+            set_button(bit, pressed);
+        }
+    }
+}
+
+void Emulator::set_button(int bit, bool pressed) {
+    if (bit < 0 || bit > 7) return;
+    if (pressed && button_held[bit]) return; // ignore OS key-repeat events, auto-repeat is ours
+    button_held[bit] = pressed;
+    button_held_frames[bit] = 0;
+    mmu.set_joypad_bit(bit, pressed);
+    uint8_t iflag = mmu.read_byte(0xFF0F); iflag |= 0x10; mmu.write_byte(0xFF0F, iflag);
+}
+
+void Emulator::update_input_repeat() {
+    if (input_config.repeat_delay <= 0 || input_config.repeat_rate <= 0) return;
+    const int rate = input_config.repeat_rate < 2 ? 2 : input_config.repeat_rate;
+    for (int bit = 0; bit < 8; ++bit) {
+        if (!button_held[bit]) continue;
+        int frames = ++button_held_frames[bit];
+        if (frames < input_config.repeat_delay) continue;
+        // 連發：每個週期的第一幀放開、其餘幀按下，讓逐幀輪詢的遊戲看到新的按下邊緣
+        int phase = (frames - input_config.repeat_delay) % rate;
+        if (phase == 0) {
+            mmu.set_joypad_bit(bit, false);
+        } else if (phase == 1) {
+            mmu.set_joypad_bit(bit, true);
             uint8_t iflag = mmu.read_byte(0xFF0F); iflag |= 0x10; mmu.write_byte(0xFF0F, iflag);
         }
     }
 }
 
+void Emulator::add_frame_hook(FrameHook* hook) {
+    if (hook && std::find(frame_hooks.begin(), frame_hooks.end(), hook) == frame_hooks.end()) {
+        frame_hooks.push_back(hook);
+    }
+}
+
+void Emulator::remove_frame_hook(FrameHook* hook) {
+    frame_hooks.erase(std::remove(frame_hooks.begin(), frame_hooks.end(), hook), frame_hooks.end());
+}
+
 void Emulator::set_ppu_lcd_start_offset(uint16_t offset) {
     mmu.get_ppu().set_lcd_start_cycle_offset(offset);
 }
 
+std::vector<uint8_t> Emulator::save_state() const {
+    StateWriter w;
+    w.put_bytes(reinterpret_cast<const uint8_t*>("GBSS"), 4);
+    w.put(SAVE_STATE_VERSION);
+    w.put(total_cycles);
+    cpu.save_state(w);
+    mmu.save_state(w);
+    return w.take();
+}
+
+bool Emulator::load_state(const std::vector<uint8_t>& data) {
+    StateReader r(data.data(), data.size());
+    uint8_t magic[4] = {};
+    uint32_t version = 0;
+    r.get_bytes(magic, 4);
+    r.get(version);
+    if (!r.good() || std::memcmp(magic, "GBSS", 4) != 0 || version != SAVE_STATE_VERSION) return false;
+
+    // 載入失敗時還原成載入前的狀態
+    std::vector<uint8_t> backup = save_state();
+    r.get(total_cycles);
+    cpu.load_state(r);
+    mmu.load_state(r);
+    if (r.good() && r.at_end()) {
+        reset_health();
+        return true;
+    }
+
+    StateReader restore(backup.data(), backup.size());
+    uint8_t skip[8];
+    restore.get_bytes(skip, 8);
+    restore.get(total_cycles);
+    cpu.load_state(restore);
+    mmu.load_state(restore);
+    return false;
+}
+
 bool Emulator::save_framebuffer_ppm(const std::string& path) const {
     const auto& fb = mmu.get_ppu().get_framebuffer();
     const int width = 160; const int height = 144;
@@ -1,25 +1,12 @@
 #include "cpu.h"
 #include <iostream>
-#include <fstream>
+#include <cstdio>
 
 CPU::CPU(MMU& mmu) : mmu(mmu) {
     reset();
-    log_file.open("cpu_log.txt");
-    // Instrumentation file (optional for instr_timing)
-    instr_cycle_log.open("instr_cycles_log.txt", std::ios::out | std::ios::trunc);
-    if (instr_cycle_log.is_open()) {
-        instr_cycle_log << "# Instruction Cycle Log\n";
-        instr_cycle_log.flush();
-    }
 }
 
 CPU::~CPU() {
-    if (log_file.is_open()) {
-        log_file.close();
-    }
-    if (instr_cycle_log.is_open()) {
-        instr_cycle_log.close();
-    }
     // std::cout << "[CPU SUMMARY] steps=" << step_count
     //           << " halt_count=" << halt_count
     //           << " halt_bug_count=" << halt_bug_count
@@ -47,7 +34,68 @@ void CPU::reset() {
     halt_bug_count = 0;
 }
 
+void CPU::save_state(StateWriter& w) const {
+    w.put(AF);
+    w.put(BC);
+    w.put(DE);
+    w.put(HL);
+    w.put(SP);
+    w.put(PC);
+    w.put(zero_flag);
+    w.put(subtract_flag);
+    w.put(half_carry_flag);
+    w.put(carry_flag);
+    w.put(ime);
+    w.put(ei_delay_pending);
+    w.put(halted);
+    w.put(just_woken_from_halt);
+    w.put(halt_bug_active);
+}
+
+void CPU::load_state(StateReader& r) {
+    r.get(AF);
+    r.get(BC);
+    r.get(DE);
+    r.get(HL);
+    r.get(SP);
+    r.get(PC);
+    r.get(zero_flag);
+    r.get(subtract_flag);
+    r.get(half_carry_flag);
+    r.get(carry_flag);
+    r.get(ime);
+    r.get(ei_delay_pending);
+    r.get(halted);
+    r.get(just_woken_from_halt);
+    r.get(halt_bug_active);
+}
+
+std::string TraceRecord::to_doctor_line() const {
+    char buf[96];
+    std::snprintf(buf, sizeof(buf),
+                  "A:%02X F:%02X B:%02X C:%02X D:%02X E:%02X H:%02X L:%02X SP:%04X PC:%04X PCMEM:%02X,%02X,%02X,%02X",
+                  af >> 8, af & 0xFF, bc >> 8, bc & 0xFF, de >> 8, de & 0xFF, hl >> 8, hl & 0xFF, sp, pc,
+                  pcmem[0], pcmem[1], pcmem[2], pcmem[3]);
+    return buf;
+}
+
+TraceRecord CPU::trace_record() {
+    sync_f_register();
+    TraceRecord record{};
+    record.pc = PC;
+    record.af = AF;
+    record.bc = BC;
+    record.de = DE;
+    record.hl = HL;
+    record.sp = SP;
+    // peek: tracing must not trigger watchpoints or read 0xFF from PPU-locked VRAM/OAM
+    for (int i = 0; i < 4; ++i) record.pcmem[i] = mmu.peek_byte(static_cast<uint16_t>(PC + i));
+    record.opcode = record.pcmem[0];
+    return record;
+}
+
 int CPU::step() {
+    last_stop = StopReason::None;
     step_count++;
     //if (step_count % 10000 == 0) {
     //    std::cout << "[CPU] Executed " << step_count << " steps, current PC=" << PC << std::endl;
@@ -55,13 +103,24 @@ int CPU::step() {
 
     // If CPU is halted, only wake when an ENABLED interrupt is pending (IE & IF)
     if (halted) {
-        uint8_t ie_reg = mmu.read_byte(0xFFFF); // Interrupt Enable
-        uint8_t if_reg = mmu.read_byte(0xFF0F); // Interrupt Flag
-        uint8_t enabled_pending = (ie_reg & if_reg) & 0x1F; // Mask to lower 5 bits
+        uint8_t enabled_pending = pending_interrupts();
 
         if (enabled_pending) {
             halted = false;
             just_woken_from_halt = true; // Mark wake for potential immediate interrupt service
+            if (ime) {
+                // IME=1: service the interrupt right away instead of executing the next opcode first.
+                // Wake-up costs 1 M-cycle, dispatch another 5 M-cycles.
+                for (int i = 0; i < 5; ++i) {
+                    if (enabled_pending & (1 << i)) {
+                        handle_interrupt(static_cast<uint8_t>(i));
+                        break;
+                    }
+                }
+                just_woken_from_halt = false;
+                mmu.update_timer_cycles(24);
+                return 24;
+            }
         } else {
             // Remain halted: consume 4 cycles (1 M-cycle)
             mmu.update_timer_cycles(4);
@@ -69,6 +128,20 @@ int CPU::step() {
         }
     }
 
+    // 斷點：在取指前停下，不執行該指令；下一次 step() 才執行
+    if (!breakpoints.empty()) {
+        bool resuming = (resume_breakpoint_pc == PC);
+        resume_breakpoint_pc = -1;
+        if (!resuming && breakpoints.count(PC)) {
+            resume_breakpoint_pc = PC;
+            last_stop = StopReason::Breakpoint;
+            return 0;
+        }
+    }
+    if (trace_sink) *trace_sink << trace_line() << '\n';
+    uint16_t watch_address = 0;
+    mmu.take_watchpoint_hit(watch_address); // drop hits from outside the CPU (debugger reads, halted polling)
+
     uint8_t opcode;
     // Inform MMU of the current PC to aid in VRAM write logging (debug only)
     // This allows MMU to log the PC of the CPU when writes to VRAM happen.
@@ -98,7 +171,13 @@ int CPU::step() {
     if (timing_test_mode) {
         burn_tcycles(4); // M1 opcode fetch
     }
+    // EI 延遲：只有在本指令執行「之前」就已排程的 EI 才會在本指令結束後生效
+    bool ei_was_pending = ei_delay_pending;
     int cycles = execute_instruction_with_cycles(opcode);
+    if (mmu.take_watchpoint_hit(watch_address)) {
+        last_stop = StopReason::Watchpoint;
+        last_watch_address = watch_address;
+    }
     if (timing_test_mode) {
         // We already burned 4 T-cycles for M1 above; exclude them from the count
         if (cycles >= 4) cycles -= 4; else cycles = 0;
@@ -108,7 +187,7 @@ int CPU::step() {
     mmu.update_timer_cycles(static_cast<uint8_t>(cycles));
 
     // Apply EI delay - EI takes effect after the next instruction executes
-    if (ei_delay_pending) {
+    if (ei_was_pending && ei_delay_pending) {
         ime = true;
         ei_delay_pending = false;
     }
@@ -117,10 +196,7 @@ int CPU::step() {
     // HALT bug simplified: we currently do NOT service interrupts when IME=0.
     // A full implementation needs special PC increment glitch; for test 02 (interrupts) we restrict to IME only.
     if (ime) {
-        uint8_t ie_reg = mmu.read_byte(0xFFFF); // Interrupt Enable
-        uint8_t if_reg = mmu.read_byte(0xFF0F); // Interrupt Flag
-
-        uint8_t interrupts = ie_reg & if_reg;
+        uint8_t interrupts = pending_interrupts(); // IE bits 5-7 are plain storage, never dispatched
         if (interrupts) {
             //std::cout << "[CPU] Interrupt pending at PC=" << std::hex << PC << std::dec << "! IE=" << (int)ie_reg << " IF=" << (int)if_reg << " combined=" << (int)interrupts << std::endl;
             halted = false; // Wake up from HALT
@@ -129,25 +205,12 @@ int CPU::step() {
             for (int i = 0; i < 5; ++i) {
                 if (interrupts & (1 << i)) {
                     //std::cout << "[CPU] Handling interrupt " << i << " at PC=" << std::hex << PC << std::dec << std::endl;
-                    // Disable interrupts and jump to handler
-                    ime = false;
-                    uint8_t if_clear = mmu.read_byte(0xFF0F) & ~(1 << i);
-                    mmu.write_byte(0xFF0F, if_clear);
-                    
-                    // Push PC to stack
-                    SP -= 2;
-                    mmu.write_byte(SP, PC & 0xFF);
-                    mmu.write_byte(SP + 1, PC >> 8);
-                    
-                    // Jump to interrupt vector
-                    static const uint16_t vectors[5] = {0x40, 0x48, 0x50, 0x58, 0x60};
-                    PC = vectors[i];
-                    
-                    // Interrupt handling takes 5 cycles (2 for delay + 3 for jump)
-                    // Interrupt service routine timing:
-                    // We already accounted for instruction cycles above; now add 5 M-cycles (20 T-cycles)
-                    mmu.update_timer_cycles(5);
-                    return cycles + 5; // Report total cycles including interrupt handling
+                    // Disable IME, clear IF bit, push PC and jump to vector (0x40/0x48/0x50/0x58/0x60)
+                    handle_interrupt(static_cast<uint8_t>(i));
+
+                    // Interrupt dispatch takes 5 M-cycles (2 wait + 2 push + 1 jump) = 20 T-cycles
+                    mmu.update_timer_cycles(20);
+                    return cycles + 20; // Report total cycles including interrupt handling
                 }
             }
         }
@@ -163,6 +226,13 @@ void CPU::execute_stop_instruction() {
     // In test context, we need to properly handle STOP
     uint8_t stop_param = mmu.read_byte(PC++);  // Read the stop parameter
 
+    // STOP resets the internal divider (DIV reads 0 afterwards), same path as a write to 0xFF04
+    mmu.write_byte(0xFF04, 0);
+
+    // CGB: with KEY1 armed, STOP performs the speed switch and execution continues
+    // (the ~2050 M-cycle pause of the switch itself is not modeled)
+    if (mmu.try_speed_switch()) return;
+
     // In headless test mode, STOP should halt execution until an interrupt occurs
     // For now, we'll continue but set a flag to indicate we're in STOP mode
     // The CPU should wake up on interrupts even when IME=0
@@ -245,21 +315,12 @@ void CPU::execute_arithmetic_instructions(uint8_t opcode) {
             int8_t e = (int8_t)mmu.read_byte(PC++);
             uint16_t sp = SP;
             uint16_t result = sp + e;
-            if (log_file.is_open()) {
-                log_file << "ADD SP,e: SP=0x" << std::hex << sp
-                         << " e=" << std::dec << (int)e
-                         << " result=0x" << std::hex << result << std::dec << std::endl;
-            }
             // Flags: Z=0, N=0, H from bit3 carry, C from bit7 carry of low-byte add (SP low + e)
             zero_flag = false;
             subtract_flag = false;
             half_carry_flag = ((sp & 0x0F) + ((uint8_t)e & 0x0F)) > 0x0F;
             carry_flag      = ((sp & 0xFF) + (uint8_t)e) > 0xFF;
             SP = result;
-            if (log_file.is_open()) {
-                log_file << "    Flags: Z=" << zero_flag << " N=" << subtract_flag
-                         << " H=" << half_carry_flag << " C=" << carry_flag << std::endl;
-            }
             break;
         }
     }
@@ -389,25 +450,17 @@ void CPU::execute_stack_instructions(uint8_t opcode) {
 
         // PUSH rr
         case 0xC5: // PUSH BC
-            SP -= 2;
-            mmu.write_byte(SP, C);
-            mmu.write_byte(SP + 1, B);
+            push16(BC);
             break;
         case 0xD5: // PUSH DE
-            SP -= 2;
-            mmu.write_byte(SP, E);
-            mmu.write_byte(SP + 1, D);
+            push16(DE);
             break;
         case 0xE5: // PUSH HL
-            SP -= 2;
-            mmu.write_byte(SP, L);
-            mmu.write_byte(SP + 1, H);
+            push16(HL);
             break;
         case 0xF5: // PUSH AF
             sync_f_register(); // Ensure F is up-to-date before push
-            SP -= 2;
-            mmu.write_byte(SP, F);
-            mmu.write_byte(SP + 1, A);
+            push16(AF);
             break;
     }
 }
@@ -498,9 +551,7 @@ void CPU::execute_call_return_instructions(uint8_t opcode) {
             {
                 uint16_t addr = mmu.read_byte(PC) | (mmu.read_byte(PC + 1) << 8);
                 PC += 2;
-                SP -= 2;
-                mmu.write_byte(SP, PC & 0xFF);
-                mmu.write_byte(SP + 1, PC >> 8);
+                push16(PC);
                 PC = addr;
             }
             break;
@@ -508,9 +559,7 @@ void CPU::execute_call_return_instructions(uint8_t opcode) {
             if (!zero_flag) {
                 uint16_t addr = mmu.read_byte(PC) | (mmu.read_byte(PC + 1) << 8);
                 PC += 2;
-                SP -= 2;
-                mmu.write_byte(SP, PC & 0xFF);
-                mmu.write_byte(SP + 1, PC >> 8);
+                push16(PC);
                 PC = addr;
             } else {
                 PC += 2;
@@ -520,9 +569,7 @@ void CPU::execute_call_return_instructions(uint8_t opcode) {
             if (zero_flag) {
                 uint16_t addr = mmu.read_byte(PC) | (mmu.read_byte(PC + 1) << 8);
                 PC += 2;
-                SP -= 2;
-                mmu.write_byte(SP, PC & 0xFF);
-                mmu.write_byte(SP + 1, PC >> 8);
+                push16(PC);
                 PC = addr;
             } else {
                 PC += 2;
@@ -532,9 +579,7 @@ void CPU::execute_call_return_instructions(uint8_t opcode) {
             if (!carry_flag) {
                 uint16_t addr = mmu.read_byte(PC) | (mmu.read_byte(PC + 1) << 8);
                 PC += 2;
-                SP -= 2;
-                mmu.write_byte(SP, PC & 0xFF);
-                mmu.write_byte(SP + 1, PC >> 8);
+                push16(PC);
                 PC = addr;
             } else {
                 PC += 2;
@@ -544,9 +589,7 @@ void CPU::execute_call_return_instructions(uint8_t opcode) {
             if (carry_flag) {
                 uint16_t addr = mmu.read_byte(PC) | (mmu.read_byte(PC + 1) << 8);
                 PC += 2;
-                SP -= 2;
-                mmu.write_byte(SP, PC & 0xFF);
-                mmu.write_byte(SP + 1, PC >> 8);
+                push16(PC);
                 PC = addr;
             } else {
                 PC += 2;
@@ -602,64 +645,29 @@ void CPU::execute_rotate_instructions(uint8_t opcode) {
 void CPU::execute_misc_instructions(uint8_t opcode) {
     switch (opcode) {
         case 0x27: // DAA
-            {
-                uint8_t correction = 0;
-                if (!subtract_flag) {
-                    // After addition/increment
-                    if (carry_flag || A > 0x99) {
-                        correction |= 0x60;
-                    }
-                    if (half_carry_flag || (A & 0x0F) > 0x09) {
-                        correction |= 0x06;
-                    }
-                    A = A + correction;
-                    if (correction & 0x60) {
-                        carry_flag = true;
-                    }
-                } else {
-                    // After subtraction/decrement
-                    if (carry_flag) {
-                        correction |= 0x60;
-                    }
-                    if (half_carry_flag) {
-                        correction |= 0x06;
-                    }
-                    A = A - correction;
-                }
-                zero_flag = (A == 0);
-                half_carry_flag = false;
-            }
+            daa();
             break;
 
         case 0x2F: // CPL
-            A = ~A;
-            subtract_flag = true;
-            half_carry_flag = true;
+            cpl();
             break;
 
         case 0x37: // SCF
-            carry_flag = true;
-            subtract_flag = false;
-            half_carry_flag = false;
-            // zero_flag 不變
+            scf();
             break;
 
         case 0x3F: // CCF
-            carry_flag = !carry_flag;
-            subtract_flag = false;
-            half_carry_flag = false;
-            // zero_flag 不變
+            ccf();
             break;
 
         case 0x76: // HALT
             {
-                uint8_t ie_reg = mmu.read_byte(0xFFFF);
-                uint8_t if_reg = mmu.read_byte(0xFF0F);
-                uint8_t pending_enabled = (ie_reg & if_reg) & 0x1F; // Only interrupts that are both requested and enabled
+                uint8_t pending_enabled = pending_interrupts(); // Only interrupts that are both requested and enabled
                 // 正確 HALT bug 條件 (Pan Docs): IME=0 且存在已啟用且已請求的中斷 (IE & IF != 0)
                 // 在此情況下：CPU 不會進入真正的 HALT；下一次 opcode 取值會重複讀取 HALT 之後的那一個位元組（造成後續指令位元組被重複執行一次）
+                // AccuracyLevel::Fast 只略過 HALT 而不重複讀取下一個位元組
                 if (!ime && pending_enabled) {
-                    halt_bug_active = true;   // 一次性：下一次取指不遞增 PC
+                    halt_bug_active = mmu.get_accuracy() == AccuracyLevel::Accurate; // 一次性：下一次取指不遞增 PC
                     halted = false;          // 不進入 halted 狀態
                     // 可選除錯輸出：
                     // std::cout << "[CPU] HALT bug (enabled) PC=" << std::hex << (PC-1) << std::dec << " IE=" << (int)ie_reg << " IF=" << (int)if_reg << std::endl;
@@ -673,20 +681,21 @@ void CPU::execute_misc_instructions(uint8_t opcode) {
 
         case 0xF3: // DI (Disable Interrupts)
             ime = false;
+            ei_delay_pending = false; // DI cancels a pending EI
             break;
         case 0xFB: // EI (Enable Interrupts) - takes effect after next instruction
             ei_delay_pending = true;
             break;
 
         // RST instructions
-        case 0xC7: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x00; break; // RST 00H
-        case 0xCF: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x08; break; // RST 08H
-        case 0xD7: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x10; break; // RST 10H
-        case 0xDF: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x18; break; // RST 18H
-        case 0xE7: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x20; break; // RST 20H
-        case 0xEF: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x28; break; // RST 28H
-        case 0xF7: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x30; break; // RST 30H
-        case 0xFF: SP -= 2; mmu.write_byte(SP, PC & 0xFF); mmu.write_byte(SP + 1, PC >> 8); PC = 0x38; break; // RST 38H
+        case 0xC7: push16(PC); PC = 0x00; break; // RST 00H
+        case 0xCF: push16(PC); PC = 0x08; break; // RST 08H
+        case 0xD7: push16(PC); PC = 0x10; break; // RST 10H
+        case 0xDF: push16(PC); PC = 0x18; break; // RST 18H
+        case 0xE7: push16(PC); PC = 0x20; break; // RST 20H
+        case 0xEF: push16(PC); PC = 0x28; break; // RST 28H
+        case 0xF7: push16(PC); PC = 0x30; break; // RST 30H
+        case 0xFF: push16(PC); PC = 0x38; break; // RST 38H
     }
 }
 
@@ -789,9 +798,6 @@ void CPU::execute_instruction(uint8_t opcode) {
 
         // Instruction not implemented yet
         default:
-            if (log_file.is_open()) {
-                log_file << "Instruction not implemented yet: 0x" << std::hex << (int)opcode << std::dec << std::endl;
-            }
             break;
     }
 
@@ -802,27 +808,6 @@ void CPU::execute_instruction(uint8_t opcode) {
 int CPU::execute_instruction_with_cycles(uint8_t opcode) {
     // Reset per-instruction burned T-cycles accounting
     timing_burned_tcycles = 0;
-    // Expected cycle table (machine cycles) for non-CB opcodes (Pan Docs). CB handled separately.
-    // NOTE: Conditional instructions use max cycles when taken; we will compute actual below.
-    static const int expected_cycles[256] = {
-        /*00*/4,/*01*/12,/*02*/8,/*03*/8,/*04*/4,/*05*/4,/*06*/8,/*07*/4, /*08*/20,/*09*/8,/*0A*/8,/*0B*/8,/*0C*/4,/*0D*/4,/*0E*/8,/*0F*/4,
-        /*10*/4,/*11*/12,/*12*/8,/*13*/8,/*14*/4,/*15*/4,/*16*/8,/*17*/4, /*18*/12,/*19*/8,/*1A*/8,/*1B*/8,/*1C*/4,/*1D*/4,/*1E*/8,/*1F*/4,
-        /*20*/12,/*21*/12,/*22*/8,/*23*/8,/*24*/4,/*25*/4,/*26*/8,/*27*/4, /*28*/12,/*29*/8,/*2A*/8,/*2B*/8,/*2C*/4,/*2D*/4,/*2E*/8,/*2F*/4,
-        /*30*/12,/*31*/12,/*32*/8,/*33*/8,/*34*/12,/*35*/12,/*36*/12,/*37*/4, /*38*/12,/*39*/8,/*3A*/8,/*3B*/8,/*3C*/4,/*3D*/4,/*3E*/8,/*3F*/4,
-        /*40*/4,/*41*/4,/*42*/4,/*43*/4,/*44*/4,/*45*/4,/*46*/8,/*47*/4, /*48*/4,/*49*/4,/*4A*/4,/*4B*/4,/*4C*/4,/*4D*/4,/*4E*/8,/*4F*/4,
-        /*50*/4,/*51*/4,/*52*/4,/*53*/4,/*54*/4,/*55*/4,/*56*/8,/*57*/4, /*58*/4,/*59*/4,/*5A*/4,/*5B*/4,/*5C*/4,/*5D*/4,/*5E*/8,/*5F*/4,
-        /*60*/4,/*61*/4,/*62*/4,/*63*/4,/*64*/4,/*65*/4,/*66*/8,/*67*/4, /*68*/4,/*69*/4,/*6A*/4,/*6B*/4,/*6C*/4,/*6D*/4,/*6E*/8,/*6F*/4,
-        /*70*/8,/*71*/8,/*72*/8,/*73*/8,/*74*/8,/*75*/8,/*76*/4,/*77*/8, /*78*/4,/*79*/4,/*7A*/4,/*7B*/4,/*7C*/4,/*7D*/4,/*7E*/8,/*7F*/4,
-        /*80*/4,/*81*/4,/*82*/4,/*83*/4,/*84*/4,/*85*/4,/*86*/8,/*87*/4, /*88*/4,/*89*/4,/*8A*/4,/*8B*/4,/*8C*/4,/*8D*/4,/*8E*/8,/*8F*/4,
-        /*90*/4,/*91*/4,/*92*/4,/*93*/4,/*94*/4,/*95*/4,/*96*/8,/*97*/4, /*98*/4,/*99*/4,/*9A*/4,/*9B*/4,/*9C*/4,/*9D*/4,/*9E*/8,/*9F*/4,
-        /*A0*/4,/*A1*/4,/*A2*/4,/*A3*/4,/*A4*/4,/*A5*/4,/*A6*/8,/*A7*/4, /*A8*/4,/*A9*/4,/*AA*/4,/*AB*/4,/*AC*/4,/*AD*/4,/*AE*/8,/*AF*/4,
-        /*B0*/4,/*B1*/4,/*B2*/4,/*B3*/4,/*B4*/4,/*B5*/4,/*B6*/8,/*B7*/4, /*B8*/4,/*B9*/4,/*BA*/4,/*BB*/4,/*BC*/4,/*BD*/4,/*BE*/8,/*BF*/4,
-        /*C0*/8,/*C1*/12,/*C2*/12,/*C3*/16,/*C4*/12,/*C5*/16,/*C6*/8,/*C7*/16, /*C8*/8,/*C9*/16,/*CA*/12,/*CB*/8,/*CC*/12,/*CD*/24,/*CE*/8,/*CF*/16,
-        /*D0*/8,/*D1*/12,/*D2*/12,/*D3*/4,/*D4*/12,/*D5*/16,/*D6*/8,/*D7*/16, /*D8*/8,/*D9*/16,/*DA*/12,/*DB*/4,/*DC*/12,/*DD*/4,/*DE*/8,/*DF*/16,
-        /*E0*/12,/*E1*/12,/*E2*/8,/*E3*/4,/*E4*/4,/*E5*/16,/*E6*/8,/*E7*/16, /*E8*/16,/*E9*/4,/*EA*/16,/*EB*/4,/*EC*/4,/*ED*/4,/*EE*/8,/*EF*/16,
-        /*F0*/12,/*F1*/12,/*F2*/8,/*F3*/4,/*F4*/4,/*F5*/16,/*F6*/8,/*F7*/16, /*F8*/12,/*F9*/8,/*FA*/16,/*FB*/4,/*FC*/4,/*FD*/4,/*FE*/8,/*FF*/16
-    };
-
     // Reference: Pan Docs / GB CPU timings. Values represent T-cycles (4T per M-cycle).
     int cycles = 4; // Default for simple register ops
 
@@ -943,20 +928,9 @@ int CPU::execute_instruction_with_cycles(uint8_t opcode) {
             if (adjusted < 0) adjusted = 0;
             real_cb_cycles = adjusted;
         }
-        int expected_cb_cycles = real_cb_cycles; // Using Pan Docs rules above
-        if (instr_cycle_log.is_open()) {
-            instr_cycle_log << "CB 0x" << std::hex << (int)cb_opcode << std::dec
-                            << " cycles=" << real_cb_cycles << " expected=" << expected_cb_cycles
-                            << (real_cb_cycles==expected_cb_cycles?" OK":" MISMATCH") << '\n';
-        }
         return real_cb_cycles;
     }
 
-    // New diagnostic entry point for CP immediate opcode
-    if (opcode == 0xFE && instr_cycle_log.is_open()) {
-        instr_cycle_log << "[DEBUG] enter execute_instruction_with_cycles 0xFE initial cycles=" << cycles << "\n";
-    }
-
     switch (opcode) {
         // 8-bit immediate loads (register only) 8 cycles
         case 0x06: case 0x0E: case 0x16: case 0x1E: case 0x26: case 0x2E: case 0x3E:
@@ -1028,9 +1002,6 @@ int CPU::execute_instruction_with_cycles(uint8_t opcode) {
         // AND d8 (0xE6), XOR d8 (0xEE), OR d8 (0xF6), CP d8 (0xFE)
         case 0xC6: case 0xCE: case 0xD6: case 0xDE: case 0xE6: case 0xEE: case 0xF6: case 0xFE:
             cycles = 8; 
-            if (opcode == 0xFE && instr_cycle_log.is_open()) {
-                instr_cycle_log << "[DEBUG] cycle assign CP d8 -> 8\n";
-            }
             break;
 
         // JR r8
@@ -1074,10 +1045,8 @@ int CPU::execute_instruction_with_cycles(uint8_t opcode) {
         default: break;
     }
 
-    // Diagnostic: confirm cycle assignment for CP d8 (0xFE)
-    if (opcode == 0xFE && instr_cycle_log.is_open()) {
-        instr_cycle_log << "[DEBUG] post-switch cycles for 0xFE=" << cycles << "\n";
-    }
+    // User-supplied timing takes precedence over the table above
+    if (cycle_overrides[opcode] >= 0) cycles = cycle_overrides[opcode];
 
     // Execute the instruction core logic
     execute_instruction(opcode);
@@ -1089,33 +1058,5 @@ int CPU::execute_instruction_with_cycles(uint8_t opcode) {
     }
     // Conditional adjustments: overwrite 'reported' but keep 'cycles' proper
     // We already computed actual above.
-
-    // Logging & mismatch detection
-    if (instr_cycle_log.is_open()) {
-        int expected = expected_cycles[opcode];
-        // For conditional instructions we stored non-taken value in expected table (Pan Docs lists non-taken); when taken we add difference.
-        // We can recompute expected dynamic for branch opcodes to reduce false positives.
-        switch (opcode) {
-            case 0x20: expected = (!zero_flag)?12:8; break;
-            case 0x28: expected = ( zero_flag)?12:8; break;
-            case 0x30: expected = (!carry_flag)?12:8; break;
-            case 0x38: expected = ( carry_flag)?12:8; break;
-            case 0xC2: expected = (!zero_flag)?16:12; break;
-            case 0xCA: expected = ( zero_flag)?16:12; break;
-            case 0xD2: expected = (!carry_flag)?16:12; break;
-            case 0xDA: expected = ( carry_flag)?16:12; break;
-            case 0xC0: expected = (!zero_flag)?20:8; break;
-            case 0xC8: expected = ( zero_flag)?20:8; break;
-            case 0xD0: expected = (!carry_flag)?20:8; break;
-            case 0xD8: expected = ( carry_flag)?20:8; break;
-            case 0xC4: expected = (!zero_flag)?24:12; break;
-            case 0xCC: expected = ( zero_flag)?24:12; break;
-            case 0xD4: expected = (!carry_flag)?24:12; break;
-            case 0xDC: expected = ( carry_flag)?24:12; break;
-        }
-        instr_cycle_log << "OP 0x" << std::hex << (int)opcode << std::dec
-                        << " cycles=" << reported << " expected=" << expected
-                        << (reported==expected?" OK":" MISMATCH") << '\n';
-    }
     return reported;
 }
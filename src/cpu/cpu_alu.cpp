@@ -95,6 +95,54 @@ void CPU::dec(uint8_t& reg) {
     half_carry_flag = ((old & 0x0F) == 0); // H is set if lower nibble was 0 (requires borrow)
 }
 
+void CPU::daa() {
+    // DAA: adjust A to valid BCD after ADD/ADC (N=0) or SUB/SBC (N=1)
+    uint8_t correction = 0;
+    if (!subtract_flag) {
+        // After addition: correct a nibble that overflowed (H/C) or exceeds 9
+        if (carry_flag || A > 0x99) {
+            correction |= 0x60;
+            carry_flag = true;
+        }
+        if (half_carry_flag || (A & 0x0F) > 0x09) {
+            correction |= 0x06;
+        }
+        A = A + correction;
+    } else {
+        // After subtraction: only undo the borrows flagged by H/C; C is unchanged
+        if (carry_flag) {
+            correction |= 0x60;
+        }
+        if (half_carry_flag) {
+            correction |= 0x06;
+        }
+        A = A - correction;
+    }
+    zero_flag = (A == 0);
+    half_carry_flag = false;
+}
+
+void CPU::cpl() {
+    // CPL: complement A; N=1, H=1, Z/C unchanged
+    A = ~A;
+    subtract_flag = true;
+    half_carry_flag = true;
+}
+
+void CPU::scf() {
+    // SCF: set carry; N=0, H=0, Z unchanged
+    carry_flag = true;
+    subtract_flag = false;
+    half_carry_flag = false;
+}
+
+void CPU::ccf() {
+    // CCF: toggle carry; N=0, H=0, Z unchanged
+    carry_flag = !carry_flag;
+    subtract_flag = false;
+    half_carry_flag = false;
+}
+
 void CPU::add_hl(uint16_t value) {
     uint32_t result = HL + value;
     subtract_flag = false;
@@ -0,0 +1,114 @@
+#include "cpu.h"
+#include <cstdarg>
+#include <cstdio>
+
+namespace {
+
+const char* const R8[8] = {"B", "C", "D", "E", "H", "L", "(HL)", "A"};
+const char* const RP[4] = {"BC", "DE", "HL", "SP"};
+const char* const RP2[4] = {"BC", "DE", "HL", "AF"};
+const char* const CC[4] = {"NZ", "Z", "NC", "C"};
+const char* const ALU[8] = {"ADD A,", "ADC A,", "SUB ", "SBC A,", "AND ", "XOR ", "OR ", "CP "};
+const char* const ROT[8] = {"RLC", "RRC", "RL", "RR", "SLA", "SRA", "SWAP", "SRL"};
+
+std::string fmt(const char* format, ...) {
+    char buf[32];
+    va_list args;
+    va_start(args, format);
+    std::vsnprintf(buf, sizeof(buf), format, args);
+    va_end(args);
+    return buf;
+}
+
+} // namespace
+
+// 以 x/y/z/p/q 位元欄位解碼（opcode = xxyyyzzz, y = ppq）
+std::pair<std::string, uint8_t> CPU::disassemble(MMU& mmu, uint16_t pc) {
+    uint8_t op = mmu.peek_byte(pc);
+    uint8_t n = mmu.peek_byte(static_cast<uint16_t>(pc + 1));
+    uint16_t nn = static_cast<uint16_t>(n | (mmu.peek_byte(static_cast<uint16_t>(pc + 2)) << 8));
+    uint16_t rel = static_cast<uint16_t>(pc + 2 + static_cast<int8_t>(n));
+    uint8_t x = op >> 6, y = (op >> 3) & 7, z = op & 7, p = y >> 1, q = y & 1;
+
+    if (op == 0xCB) {
+        uint8_t cb_x = n >> 6, cb_y = (n >> 3) & 7, cb_z = n & 7;
+        static const char* const BIT_OPS[4] = {nullptr, "BIT", "RES", "SET"};
+        if (cb_x == 0) return {fmt("%s %s", ROT[cb_y], R8[cb_z]), 2};
+        return {fmt("%s %d,%s", BIT_OPS[cb_x], cb_y, R8[cb_z]), 2};
+    }
+
+    switch (x) {
+        case 0:
+            switch (z) {
+                case 0:
+                    if (y == 0) return {"NOP", 1};
+                    if (y == 1) return {fmt("LD ($%04X),SP", nn), 3};
+                    if (y == 2) return {"STOP", 2};
+                    if (y == 3) return {fmt("JR $%04X", rel), 2};
+                    return {fmt("JR %s,$%04X", CC[y - 4], rel), 2};
+                case 1:
+                    if (q == 0) return {fmt("LD %s,$%04X", RP[p], nn), 3};
+                    return {fmt("ADD HL,%s", RP[p]), 1};
+                case 2: {
+                    static const char* const IND[4] = {"(BC)", "(DE)", "(HL+)", "(HL-)"};
+                    if (q == 0) return {fmt("LD %s,A", IND[p]), 1};
+                    return {fmt("LD A,%s", IND[p]), 1};
+                }
+                case 3: return {fmt("%s %s", q == 0 ? "INC" : "DEC", RP[p]), 1};
+                case 4: return {fmt("INC %s", R8[y]), 1};
+                case 5: return {fmt("DEC %s", R8[y]), 1};
+                case 6: return {fmt("LD %s,$%02X", R8[y], n), 2};
+                default: {
+                    static const char* const MISC[8] = {"RLCA", "RRCA", "RLA", "RRA", "DAA", "CPL", "SCF", "CCF"};
+                    return {MISC[y], 1};
+                }
+            }
+        case 1:
+            if (op == 0x76) return {"HALT", 1};
+            return {fmt("LD %s,%s", R8[y], R8[z]), 1};
+        case 2:
+            return {fmt("%s%s", ALU[y], R8[z]), 1};
+        default:
+            break;
+    }
+
+    // x == 3
+    switch (z) {
+        case 0:
+            if (y < 4) return {fmt("RET %s", CC[y]), 1};
+            if (y == 4) return {fmt("LDH ($FF%02X),A", n), 2};
+            if (y == 5) return {fmt("ADD SP,%d", static_cast<int8_t>(n)), 2};
+            if (y == 6) return {fmt("LDH A,($FF%02X)", n), 2};
+            return {fmt("LD HL,SP%+d", static_cast<int8_t>(n)), 2};
+        case 1:
+            if (q == 0) return {fmt("POP %s", RP2[p]), 1};
+            if (p == 0) return {"RET", 1};
+            if (p == 1) return {"RETI", 1};
+            if (p == 2) return {"JP HL", 1};
+            return {"LD SP,HL", 1};
+        case 2:
+            if (y < 4) return {fmt("JP %s,$%04X", CC[y], nn), 3};
+            if (y == 4) return {"LD ($FF00+C),A", 1};
+            if (y == 5) return {fmt("LD ($%04X),A", nn), 3};
+            if (y == 6) return {"LD A,($FF00+C)", 1};
+            return {fmt("LD A,($%04X)", nn), 3};
+        case 3:
+            if (y == 0) return {fmt("JP $%04X", nn), 3};
+            if (y == 6) return {"DI", 1};
+            if (y == 7) return {"EI", 1};
+            break; // 0xCB 已處理；其餘為非法 opcode
+        case 4:
+            if (y < 4) return {fmt("CALL %s,$%04X", CC[y], nn), 3};
+            break;
+        case 5:
+            if (q == 0) return {fmt("PUSH %s", RP2[p]), 1};
+            if (p == 0) return {fmt("CALL $%04X", nn), 3};
+            break;
+        case 6:
+            return {fmt("%s$%02X", ALU[y], n), 2};
+        default:
+            return {fmt("RST $%02X", y * 8), 1};
+    }
+    // 非法 opcode（0xD3、0xDB、0xDD、0xE3、0xE4、0xEB-0xED、0xF4、0xFC、0xFD）
+    return {fmt("DB $%02X", op), 1};
+}
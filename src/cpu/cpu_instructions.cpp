@@ -1,8 +1,6 @@
 // 指令解碼與執行相關實作，從 cpu.cpp 拆出
 #include "cpu.h"
 #include <iostream>
-#include <unordered_map>
-#include <functional>
 
 // 指令解碼與執行相關實作，預留檔案（目前主要邏輯仍在 cpu.cpp、cpu_alu.cpp、cpu_cb.cpp、cpu_rotate.cpp、cpu_bits.cpp）。
 // 若未來要進一步重構，建議把 cpu.cpp 的大 switch 搬移到這裡，並保持每個領域（ALU/CB/rotate/bits）在各自檔案內。
@@ -12,79 +10,7 @@
 // void CPU::execute_cb_instruction(uint8_t opcode) { ... }
 // 其他 ALU/flag/bit 操作等
 
-// Alternative implementation using std::unordered_map (for demonstration)
-// This approach allows dynamic instruction registration but has slightly worse performance than switch-case
-void CPU::execute_load_instructions_map(uint8_t opcode) {
-    static std::unordered_map<uint8_t, std::function<void()>> load_instruction_map = {
-        // 8-bit immediate loads (LD r, n)
-        {0x06, [this]() { B = mmu.read_byte(PC++); }},
-        {0x0E, [this]() { C = mmu.read_byte(PC++); }},
-        {0x16, [this]() { D = mmu.read_byte(PC++); }},
-        {0x1E, [this]() { E = mmu.read_byte(PC++); }},
-        {0x26, [this]() { H = mmu.read_byte(PC++); }},
-        {0x2E, [this]() { L = mmu.read_byte(PC++); }},
-        {0x36, [this]() {
-            uint8_t value = mmu.read_byte(PC++);
-            if (timing_test_mode) {
-                burn_tcycles(4);
-                burn_tcycles(2);
-                mmu.write_byte(HL, value);
-                burn_tcycles(2);
-            } else {
-                mmu.write_byte(HL, value);
-            }
-        }},
-        {0x3E, [this]() { A = mmu.read_byte(PC++); }},
-
-        // 8-bit register loads (LD r, r) - showing first few as example
-        {0x40, [this]() { B = B; }},
-        {0x41, [this]() { B = C; }},
-        {0x42, [this]() { B = D; }},
-        // ... would continue for all register combinations
-
-        // 16-bit loads
-        {0x01, [this]() { C = mmu.read_byte(PC++); B = mmu.read_byte(PC++); }},
-        {0x11, [this]() { E = mmu.read_byte(PC++); D = mmu.read_byte(PC++); }},
-        {0x21, [this]() { L = mmu.read_byte(PC++); H = mmu.read_byte(PC++); }},
-        {0x31, [this]() {
-            uint8_t low = mmu.read_byte(PC++);
-            uint8_t high = mmu.read_byte(PC++);
-            SP = low | (high << 8);
-        }},
-
-        // LDH instructions
-        {0xE0, [this]() {
-            uint8_t offset = mmu.read_byte(PC++);
-            if (timing_test_mode) {
-                burn_tcycles(4);
-                burn_tcycles(2);
-                mmu.write_byte(0xFF00 + offset, A);
-                burn_tcycles(2);
-            } else {
-                mmu.write_byte(0xFF00 + offset, A);
-            }
-        }},
-        {0xF0, [this]() {
-            uint8_t imm = mmu.read_byte(PC++);
-            if (timing_test_mode) {
-                burn_tcycles(4);
-                A = mmu.read_byte(0xFF00 + imm);
-                burn_tcycles(4);
-            } else {
-                A = mmu.read_byte(0xFF00 + imm);
-            }
-        }}
-    };
-
-    auto it = load_instruction_map.find(opcode);
-    if (it != load_instruction_map.end()) {
-        it->second(); // Execute the instruction
-    } else {
-        // Unknown load instruction - do nothing
-    }
-}
-
-// Load instructions implementation (current switch-case approach)
+// Load instructions implementation
 void CPU::execute_load_instructions(uint8_t opcode) {
     switch (opcode) {
         // 8-bit immediate loads (LD r, n)
@@ -477,21 +403,12 @@ void CPU::execute_load_instructions(uint8_t opcode) {
                 int8_t e = (int8_t)mmu.read_byte(PC++);
                 uint16_t sp = SP;
                 uint16_t result = sp + e;
-                if (log_file.is_open()) {
-                    log_file << "LD HL,SP+e: SP=0x" << std::hex << sp
-                             << " e=" << std::dec << (int)e
-                             << " result=0x" << std::hex << result << std::dec << std::endl;
-                }
                 // Flags: Z=0, N=0, H from bit3 carry, C from bit7 carry of low-byte add (SP low + e)
                 zero_flag = false;
                 subtract_flag = false;
                 half_carry_flag = ((sp & 0x0F) + ((uint8_t)e & 0x0F)) > 0x0F;
                 carry_flag      = ((sp & 0xFF) + (uint8_t)e) > 0xFF;
                 HL = result;
-                if (log_file.is_open()) {
-                    log_file << "    Flags: Z=" << zero_flag << " N=" << subtract_flag
-                             << " H=" << half_carry_flag << " C=" << carry_flag << std::endl;
-                }
             }
             break;
         case 0xF9: // LD SP, HL
@@ -5,10 +5,15 @@ void CPU::handle_interrupt(uint8_t interrupt_type) {
 
     ime = false; // Disable interrupts
 
+    // HALT bug still pending (EI; HALT with an interrupt already requested): the un-incremented
+    // fetch never happens; the return address is the HALT itself, so HALT runs again after RETI
+    if (halt_bug_active) {
+        PC--;
+        halt_bug_active = false;
+    }
+
     // Push PC to stack
-    SP -= 2;
-    mmu.write_byte(SP, PC & 0xFF);
-    mmu.write_byte(SP + 1, PC >> 8);
+    push16(PC);
 
     // Jump to interrupt vector
     switch (interrupt_type) {
@@ -26,8 +31,14 @@ void CPU::handle_interrupt(uint8_t interrupt_type) {
 }
 
 void CPU::rst(uint8_t addr) {
-    SP -= 2;
-    mmu.write_byte(SP, PC & 0xFF);
-    mmu.write_byte(SP + 1, PC >> 8);
+    push16(PC);
     PC = addr;
 }
+
+// 硬體順序：SP 先減 1 寫高位元組，再減 1 寫低位元組（低位元組落在較低位址）
+void CPU::push16(uint16_t value) {
+    SP--;
+    mmu.write_byte(SP, value >> 8);
+    SP--;
+    mmu.write_byte(SP, value & 0xFF);
+}
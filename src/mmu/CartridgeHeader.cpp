@@ -0,0 +1,106 @@
+#include "CartridgeHeader.h"
+#include <cstdio>
+
+bool CartridgeHeader::parse(const std::vector<uint8_t>& rom_data, CartridgeHeader& out) {
+    if (rom_data.size() < 0x0150) return false;
+    CartridgeHeader h;
+    h.cgb_flag = rom_data[0x0143];
+    int title_end = h.is_cgb() ? 0x0142 : 0x0143;
+    for (int i = 0x0134; i <= title_end; ++i) {
+        if (rom_data[i] == 0) break;
+        h.title += static_cast<char>(rom_data[i]);
+    }
+    h.sgb_flag = rom_data[0x0146];
+    h.cartridge_type = rom_data[0x0147];
+    h.rom_size_code = rom_data[0x0148];
+    h.ram_size_code = rom_data[0x0149];
+    h.destination = rom_data[0x014A];
+    if (rom_data[0x014B] == 0x33) {
+        h.licensee = {static_cast<char>(rom_data[0x0144]), static_cast<char>(rom_data[0x0145])};
+    } else {
+        char buf[3];
+        std::snprintf(buf, sizeof(buf), "%02X", rom_data[0x014B]);
+        h.licensee = buf;
+    }
+    h.version = rom_data[0x014C];
+    h.header_checksum = rom_data[0x014D];
+    h.global_checksum = static_cast<uint16_t>((rom_data[0x014E] << 8) | rom_data[0x014F]);
+    out = h;
+    return true;
+}
+
+std::string CartridgeHeader::type_name() const {
+    switch (cartridge_type) {
+        case 0x00: return "ROM ONLY";
+        case 0x01: return "MBC1";
+        case 0x02: return "MBC1+RAM";
+        case 0x03: return "MBC1+RAM+BATTERY";
+        case 0x05: return "MBC2";
+        case 0x06: return "MBC2+BATTERY";
+        case 0x08: return "ROM+RAM";
+        case 0x09: return "ROM+RAM+BATTERY";
+        case 0x0F: return "MBC3+TIMER+BATTERY";
+        case 0x10: return "MBC3+TIMER+RAM+BATTERY";
+        case 0x11: return "MBC3";
+        case 0x12: return "MBC3+RAM";
+        case 0x13: return "MBC3+RAM+BATTERY";
+        case 0x19: return "MBC5";
+        case 0x1A: return "MBC5+RAM";
+        case 0x1B: return "MBC5+RAM+BATTERY";
+        case 0x1C: return "MBC5+RUMBLE";
+        case 0x1D: return "MBC5+RUMBLE+RAM";
+        case 0x1E: return "MBC5+RUMBLE+RAM+BATTERY";
+        default: return "UNKNOWN";
+    }
+}
+
+std::string CartridgeHeader::rom_size() const {
+    switch (rom_size_code) {
+        case 0x00: return "32KB";
+        case 0x01: return "64KB";
+        case 0x02: return "128KB";
+        case 0x03: return "256KB";
+        case 0x04: return "512KB";
+        case 0x05: return "1MB";
+        case 0x06: return "2MB";
+        case 0x07: return "4MB";
+        case 0x08: return "8MB";
+        case 0x52: return "1.1MB";
+        case 0x53: return "1.2MB";
+        case 0x54: return "1.5MB";
+        default: return "UNKNOWN";
+    }
+}
+
+std::string CartridgeHeader::ram_size() const {
+    switch (ram_size_code) {
+        case 0x00: return "None";
+        case 0x01: return "2KB";
+        case 0x02: return "8KB";
+        case 0x03: return "32KB";
+        case 0x04: return "128KB";
+        case 0x05: return "64KB";
+        default: return "UNKNOWN";
+    }
+}
+
+size_t CartridgeHeader::rom_size_bytes() const {
+    if (rom_size_code <= 0x08) return static_cast<size_t>(32 * 1024) << rom_size_code;
+    switch (rom_size_code) {
+        case 0x52: return 72 * 16 * 1024;
+        case 0x53: return 80 * 16 * 1024;
+        case 0x54: return 96 * 16 * 1024;
+        default: return 0;
+    }
+}
+
+size_t CartridgeHeader::ram_size_bytes() const {
+    switch (ram_size_code) {
+        case 0x01: return 2 * 1024;
+        case 0x02: return 8 * 1024;
+        case 0x03: return 32 * 1024;
+        case 0x04: return 128 * 1024;
+        case 0x05: return 64 * 1024;
+        default: return 0;
+    }
+}
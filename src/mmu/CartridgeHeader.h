@@ -0,0 +1,31 @@
+#pragma once
+
+#include <cstdint>
+#include <cstddef>
+#include <string>
+#include <vector>
+
+// Decoded cartridge header (0x0100-0x014F)
+struct CartridgeHeader {
+    std::string title;            // 0x0134-0x0143 up to the first NUL (0x0134-0x0142 when 0x0143 is a CGB flag)
+    uint8_t cgb_flag = 0;         // 0x0143: 0x80 = CGB enhanced, 0xC0 = CGB only
+    uint8_t sgb_flag = 0;         // 0x0146: 0x03 = SGB functions
+    uint8_t cartridge_type = 0;   // 0x0147: MBC and extra hardware (see type_name())
+    uint8_t rom_size_code = 0;    // 0x0148
+    uint8_t ram_size_code = 0;    // 0x0149
+    uint8_t destination = 0;      // 0x014A: 0x00 = Japan
+    std::string licensee;         // new licensee code (0x0144-0x0145) when 0x014B is 0x33, else 0x014B as two hex digits
+    uint8_t version = 0;          // 0x014C
+    uint8_t header_checksum = 0;  // 0x014D
+    uint16_t global_checksum = 0; // 0x014E-0x014F, big-endian
+
+    // Fails (leaving out untouched) if the data is too short to contain a header
+    static bool parse(const std::vector<uint8_t>& rom_data, CartridgeHeader& out);
+
+    std::string type_name() const; // e.g. "MBC1+RAM+BATTERY", "UNKNOWN"
+    std::string rom_size() const;  // e.g. "32KB", "UNKNOWN"
+    std::string ram_size() const;  // e.g. "8KB", "None", "UNKNOWN"
+    size_t rom_size_bytes() const; // 0 for unknown codes
+    size_t ram_size_bytes() const; // header RAM only (MBC2's built-in 512 x 4 bits is not included)
+    bool is_cgb() const { return cgb_flag == 0x80 || cgb_flag == 0xC0; }
+};
@@ -2,8 +2,10 @@
 
 MBC::MBC(MBCType type, const std::vector<uint8_t>& rom_ref, std::vector<uint8_t>& external_ram_ref)
     : mbc_type(type), mbc_ram_enabled(false), mbc_rom_bank(1), mbc_ram_bank(0), mbc_mode(0),
-      rom(rom_ref), external_ram(external_ram_ref)
+      rtc_last_update(std::time(nullptr)), rom(rom_ref), external_ram(external_ram_ref)
 {
+    // ROM+RAM (0x08/0x09) 沒有 MBC，也就沒有 RAM enable 閂鎖：RAM 永遠可存取
+    if (type == 0x08 || type == 0x09) mbc_ram_enabled = true;
 }
 
 void MBC::handle_write(uint16_t address, uint8_t value) {
@@ -43,14 +45,60 @@ uint8_t MBC::get_rom_bank(uint16_t address) const {
 }
 
 uint8_t MBC::get_ram_bank(uint16_t address) const {
+    if (rtc_selected()) {
+        if (!mbc_ram_enabled) return 0xFF;
+        return rtc_latched[mbc_ram_bank - 0x08];
+    }
     if (!mbc_ram_enabled || external_ram.empty()) return 0xFF;
-    uint16_t ram_address = get_ram_address(address);
-    if (ram_address < external_ram.size()) return external_ram[ram_address];
+    uint32_t ram_address = get_ram_address(address);
+    if (ram_address < external_ram.size()) {
+        if (is_mbc2()) return external_ram[ram_address] & 0x0F; // only the low nibble exists
+        return external_ram[ram_address];
+    }
     return 0xFF;
 }
 
+MBC::State MBC::get_state() const {
+    State state;
+    state.ram_enabled = mbc_ram_enabled;
+    state.rom_bank = mbc_rom_bank;
+    state.ram_bank = mbc_ram_bank;
+    state.mode = mbc_mode;
+    state.rtc = rtc_regs;
+    state.rtc_latched = rtc_latched;
+    return state;
+}
+
+void MBC::set_state(const State& state) {
+    mbc_ram_enabled = state.ram_enabled;
+    mbc_rom_bank = state.rom_bank;
+    mbc_ram_bank = state.ram_bank;
+    mbc_mode = state.mode;
+    rtc_regs = state.rtc;
+    rtc_latched = state.rtc_latched;
+    rtc_last_update = std::time(nullptr);
+}
+
+void MBC::write_ram(uint16_t address, uint8_t value) {
+    if (rtc_selected()) {
+        if (!mbc_ram_enabled) return;
+        // 寫入 RTC 前先把經過的時間結算，之後從新值重新計時
+        static const uint8_t rtc_masks[5] = {0x3F, 0x3F, 0x1F, 0xFF, 0xC1};
+        update_rtc();
+        uint8_t index = mbc_ram_bank - 0x08;
+        rtc_regs[index] = value & rtc_masks[index];
+        rtc_latched[index] = rtc_regs[index];
+        return;
+    }
+    if (!mbc_ram_enabled || external_ram.empty()) return;
+    uint32_t ram_address = get_ram_address(address);
+    if (ram_address < external_ram.size()) external_ram[ram_address] = is_mbc2() ? (value & 0x0F) : value;
+}
+
 void MBC::handle_mbc1_write(uint16_t address, uint8_t value) {
-    if (address >= 0x2000 && address <= 0x3FFF) {
+    if (address <= 0x1FFF) {
+        mbc_ram_enabled = (value & 0x0F) == 0x0A;
+    } else if (address >= 0x2000 && address <= 0x3FFF) {
         uint8_t bank = value & 0x1F; if (bank == 0) bank = 1; mbc_rom_bank = (mbc_rom_bank & 0x60) | bank;
     } else if (address >= 0x4000 && address <= 0x5FFF) {
         if (mbc_mode == 0) { mbc_rom_bank = (mbc_rom_bank & 0x1F) | ((value & 0x03) << 5); }
@@ -61,28 +109,68 @@ void MBC::handle_mbc1_write(uint16_t address, uint8_t value) {
 }
 
 void MBC::handle_mbc2_write(uint16_t address, uint8_t value) {
-    if (address >= 0x2000 && address <= 0x3FFF) {
-        uint8_t bank = value & 0x0F; if (bank == 0) bank = 1; mbc_rom_bank = bank;
+    // 0x0000-0x3FFF: address bit 8 selects the register (0 = RAM enable, 1 = ROM bank)
+    if (address <= 0x3FFF) {
+        if (address & 0x0100) {
+            uint8_t bank = value & 0x0F; if (bank == 0) bank = 1; mbc_rom_bank = bank;
+        } else {
+            mbc_ram_enabled = (value & 0x0F) == 0x0A;
+        }
     }
 }
 
 void MBC::handle_mbc3_write(uint16_t address, uint8_t value) {
-    if (address >= 0x2000 && address <= 0x3FFF) {
+    if (address <= 0x1FFF) {
+        mbc_ram_enabled = (value & 0x0F) == 0x0A;
+    } else if (address >= 0x2000 && address <= 0x3FFF) {
         uint8_t bank = value & 0x7F; if (bank == 0) bank = 1; mbc_rom_bank = bank;
     } else if (address >= 0x4000 && address <= 0x5FFF) {
-        mbc_ram_bank = value;
+        mbc_ram_bank = value; // 0x00-0x03: RAM bank, 0x08-0x0C: RTC register
+    } else if (address >= 0x6000 && address <= 0x7FFF) {
+        // Latch clock data: writing 0x00 then 0x01 copies the live RTC registers
+        if (has_rtc() && rtc_latch_prev == 0x00 && value == 0x01) {
+            update_rtc();
+            rtc_latched = rtc_regs;
+        }
+        rtc_latch_prev = value;
+    }
+}
+
+void MBC::update_rtc() {
+    std::time_t now = std::time(nullptr);
+    std::time_t elapsed = now - rtc_last_update;
+    rtc_last_update = now;
+    if (elapsed <= 0 || (rtc_regs[4] & 0x40)) return; // DH bit 6 = halt
+
+    uint64_t days = rtc_regs[3] | ((rtc_regs[4] & 0x01) << 8);
+    uint64_t total = days * 86400 + rtc_regs[2] * 3600 + rtc_regs[1] * 60 + rtc_regs[0] + static_cast<uint64_t>(elapsed);
+
+    rtc_regs[0] = total % 60;
+    rtc_regs[1] = (total / 60) % 60;
+    rtc_regs[2] = (total / 3600) % 24;
+    days = total / 86400;
+    if (days > 0x1FF) {
+        rtc_regs[4] |= 0x80; // day counter carry
+        days &= 0x1FF;
     }
+    rtc_regs[3] = days & 0xFF;
+    rtc_regs[4] = (rtc_regs[4] & 0xFE) | ((days >> 8) & 0x01);
 }
 
 void MBC::handle_mbc5_write(uint16_t address, uint8_t value) {
-    if (address >= 0x2000 && address <= 0x2FFF) {
+    if (address <= 0x1FFF) {
+        mbc_ram_enabled = (value & 0x0F) == 0x0A;
+    } else if (address >= 0x2000 && address <= 0x2FFF) {
         mbc_rom_bank = (mbc_rom_bank & 0x0100) | value;
     } else if (address >= 0x3000 && address <= 0x3FFF) {
         mbc_rom_bank = (mbc_rom_bank & 0x00FF) | ((value & 0x01) << 8);
+    } else if (address >= 0x4000 && address <= 0x5FFF) {
+        mbc_ram_bank = value & 0x0F; // RAM bank 0x00-0x0F
     }
 }
 
-uint16_t MBC::get_ram_address(uint16_t address) const {
-    uint16_t bank_offset = mbc_ram_bank * 0x2000; // 8KB per bank
+uint32_t MBC::get_ram_address(uint16_t address) const {
+    if (is_mbc2()) return (address - 0xA000) & 0x01FF; // built-in 512 x 4-bit RAM, mirrored across A000-BFFF
+    uint32_t bank_offset = mbc_ram_bank * 0x2000u; // 8KB per bank (MBC5 up to 16 banks = 128KB)
     return (address - 0xA000) + bank_offset;
 }
\ No newline at end of file
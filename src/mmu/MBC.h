@@ -2,6 +2,8 @@
 
 #include <cstdint>
 #include <vector>
+#include <array>
+#include <ctime>
 
 enum MBCType {
     MBC_NONE = 0x00,
@@ -10,11 +12,11 @@ enum MBCType {
     MBC1_RAM_BATTERY = 0x03,
     MBC2 = 0x05,
     MBC2_BATTERY = 0x06,
-    MBC3 = 0x0F,
-    MBC3_TIMER_BATTERY = 0x10,
-    MBC3_RAM = 0x11,
-    MBC3_RAM_BATTERY = 0x12,
-    MBC3_TIMER_RAM_BATTERY = 0x13,
+    MBC3_TIMER_BATTERY = 0x0F,
+    MBC3_TIMER_RAM_BATTERY = 0x10,
+    MBC3 = 0x11,
+    MBC3_RAM = 0x12,
+    MBC3_RAM_BATTERY = 0x13,
     MBC5 = 0x19,
     MBC5_RAM = 0x1A,
     MBC5_RAM_BATTERY = 0x1B,
@@ -25,14 +27,34 @@ enum MBCType {
 
 class MBC {
 public:
+    // Banking registers only (no ROM/RAM contents), for snapshotting cartridge state
+    struct State {
+        bool ram_enabled = false;
+        uint16_t rom_bank = 1;
+        uint8_t ram_bank = 0;
+        uint8_t mode = 0;
+        // MBC3 RTC: S, M, H, DL, DH (live and latched copies)
+        std::array<uint8_t, 5> rtc{};
+        std::array<uint8_t, 5> rtc_latched{};
+    };
+
     MBC(MBCType type, const std::vector<uint8_t>& rom, std::vector<uint8_t>& external_ram);
     ~MBC() = default;
 
     void handle_write(uint16_t address, uint8_t value);
     uint8_t get_rom_bank(uint16_t address) const;
     uint8_t get_ram_bank(uint16_t address) const;
+    void write_ram(uint16_t address, uint8_t value);
 
     bool is_ram_enabled() const { return mbc_ram_enabled; }
+    // Whether 0xA000-0xBFFF currently reaches something: enabled RAM, or an MBC3 RTC register
+    bool ram_mapped() const { return mbc_ram_enabled && (rtc_selected() || !external_ram.empty()); }
+
+    bool is_mbc2() const { return mbc_type == MBC2 || mbc_type == MBC2_BATTERY; }
+    bool has_rtc() const { return mbc_type == MBC3_TIMER_BATTERY || mbc_type == MBC3_TIMER_RAM_BATTERY; }
+
+    State get_state() const;
+    void set_state(const State& state);
 
 private:
     MBCType mbc_type;
@@ -41,6 +63,12 @@ private:
     uint8_t mbc_ram_bank;
     uint8_t mbc_mode;
 
+    // MBC3 real-time clock
+    std::array<uint8_t, 5> rtc_regs{};     // live S, M, H, DL, DH
+    std::array<uint8_t, 5> rtc_latched{};  // values visible at 0xA000 after latching
+    uint8_t rtc_latch_prev = 0xFF;         // last value written to 0x6000-0x7FFF
+    std::time_t rtc_last_update;           // wall-clock time the live registers were last advanced
+
     const std::vector<uint8_t>& rom;
     std::vector<uint8_t>& external_ram;
 
@@ -49,5 +77,7 @@ private:
     void handle_mbc3_write(uint16_t address, uint8_t value);
     void handle_mbc5_write(uint16_t address, uint8_t value);
 
-    uint16_t get_ram_address(uint16_t address) const;
+    uint32_t get_ram_address(uint16_t address) const;
+    bool rtc_selected() const { return has_rtc() && mbc_ram_bank >= 0x08 && mbc_ram_bank <= 0x0C; }
+    void update_rtc();
 };
\ No newline at end of file
@@ -30,15 +30,19 @@ bool Timer::update_cycles(uint8_t cycles) {
         internal_counter = (internal_counter + 1) & 0xFFFF;
         divider = (internal_counter >> 8) & 0xFF;
 
-        // Count down overflow delay (disabled for test compatibility)
-        // if (tima_overflow_pending) {
-        //     tima_overflow_delay--;
-        //     if (tima_overflow_delay == 0) {
-        //         timer_counter = timer_modulo;
-        //         tima_overflow_pending = false;
-        //     }
-        //     continue;
-        // }
+        // DIV-APU: falling edge of DIV bit 4 clocks the APU frame sequencer
+        if (((prev_counter >> div_apu_bit) & 1) && !((internal_counter >> div_apu_bit) & 1)) {
+            ++div_apu_events;
+        }
+
+        // Overflow delay: TIMA reads 0x00 for 4 T-cycles, then reloads from TMA and requests IF
+        if (tima_overflow_pending) {
+            if (--tima_overflow_delay == 0) {
+                timer_counter = timer_modulo;
+                tima_overflow_pending = false;
+                interrupt_set = true;
+            }
+        }
 
         // Check timer if enabled
         if (!(timer_control & 0x04)) continue;
@@ -51,12 +55,9 @@ bool Timer::update_cycles(uint8_t cycles) {
         if (prev_bit && !curr_bit) {
             timer_counter++;
             if (timer_counter == 0x00) {
-                // Overflow: TIMA wraps to 0x00, IF is set immediately
-                // Then TIMA reloads from TMA immediately (no delay for test compatibility)
-                interrupt_set = true;
-                timer_counter = timer_modulo; // Immediate reload for test compatibility
-                // tima_overflow_pending = true;
-                // tima_overflow_delay = 4;
+                // Overflow: TIMA stays 0x00 for one M-cycle before the TMA reload and IF request
+                tima_overflow_pending = true;
+                tima_overflow_delay = 4;
             }
         }
     }
@@ -77,7 +78,7 @@ void Timer::set_tac(uint8_t value) {
     bool new_bit = ((internal_counter >> new_bit_idx) & 1) != 0;
 
     bool falling_edge = false;
-    if (old_enabled) {
+    if (old_enabled && write_glitches) {
         bool from = old_bit;
         bool to = new_enabled ? new_bit : false;
         if (from && !to) falling_edge = true;
@@ -85,26 +86,37 @@ void Timer::set_tac(uint8_t value) {
 
     timer_control = new_tac;
 
-    if (falling_edge) { // Removed tima_overflow_pending check for test compatibility
+    if (falling_edge) {
         ++timer_counter;
         if (timer_counter == 0x00) {
-            timer_counter = timer_modulo; // Immediate reload
-            // tima_overflow_pending = true;
-            // tima_overflow_delay = 4;
+            tima_overflow_pending = true;
+            tima_overflow_delay = 4;
         }
     }
 }
 
 void Timer::set_divider(uint8_t value) {
-    // DIV write resets divider counter and TIMA to TMA value
+    // Any DIV write resets the whole internal counter; the value written is ignored.
+    // If the TAC-selected bit was 1, the reset is a falling edge and TIMA ticks once.
+    bool falling_edge = write_glitches && (timer_control & 0x04) && ((internal_counter >> timer_bit_for_tac(timer_control)) & 1);
+    // Same for DIV bit 4: resetting while it is set clocks the frame sequencer early
+    if ((internal_counter >> div_apu_bit) & 1) ++div_apu_events;
     internal_counter = 0;
     divider = 0;
-    timer_counter = timer_modulo; // TIMA is also reset to TMA
+    if (falling_edge) {
+        ++timer_counter;
+        if (timer_counter == 0x00) {
+            tima_overflow_pending = true;
+            tima_overflow_delay = 4;
+        }
+    }
 }
 
 void Timer::set_timer_counter(uint8_t value) {
-    // Simplified: just set TIMA value (no overflow pending logic)
+    // Writing TIMA during the overflow delay cancels the pending TMA reload and interrupt
     timer_counter = value;
+    tima_overflow_pending = false;
+    tima_overflow_delay = 0;
 }
 
 void Timer::set_timer_modulo(uint8_t value) {
@@ -112,6 +124,28 @@ void Timer::set_timer_modulo(uint8_t value) {
     // If during pending, new TMA will be used at reload (no extra action needed)
 }
 
+void Timer::save_state(StateWriter& w) const {
+    w.put(internal_counter);
+    w.put(divider);
+    w.put(timer_counter);
+    w.put(timer_modulo);
+    w.put(timer_control);
+    w.put(tima_overflow_pending);
+    w.put(tima_overflow_delay);
+    w.put(div_apu_events);
+}
+
+void Timer::load_state(StateReader& r) {
+    r.get(internal_counter);
+    r.get(divider);
+    r.get(timer_counter);
+    r.get(timer_modulo);
+    r.get(timer_control);
+    r.get(tima_overflow_pending);
+    r.get(tima_overflow_delay);
+    r.get(div_apu_events);
+}
+
 void Timer::force_align_cycle_boundary() {
     internal_counter &= 0xFFFC; // clear lower 2 bits (align to 4T)
     divider = (internal_counter >> 8) & 0xFF;
@@ -1,6 +1,7 @@
 #pragma once
 
 #include <cstdint>
+#include "save_state.h"
 
 class Timer {
 public:
@@ -20,6 +21,13 @@ public:
     uint8_t get_timer_control() const { return timer_control; }
     uint8_t get_cycle_mod4() const { return (internal_counter & 0x3); }
 
+    // DIV-APU events (falling edges of DIV bit 4) since the last call; each one clocks the APU frame sequencer
+    int take_div_apu_events() { int n = div_apu_events; div_apu_events = 0; return n; }
+    // CGB double speed: DIV runs twice as fast, so the frame sequencer follows DIV bit 5 instead
+    void set_double_speed(bool on) { div_apu_bit = on ? DIV_APU_BIT + 1 : DIV_APU_BIT; }
+    // When off, DIV/TAC writes never clock TIMA through the falling-edge glitch
+    void set_write_glitches(bool on) { write_glitches = on; }
+
     // Write registers
     void set_divider(uint8_t value);
     void set_timer_counter(uint8_t value);
@@ -28,6 +36,10 @@ public:
     // Special functions
     void force_align_cycle_boundary();
 
+    // Save state
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
+
 private:
     uint16_t internal_counter;
     uint8_t divider;
@@ -36,6 +48,12 @@ private:
     uint8_t timer_control;
     bool tima_overflow_pending;
     uint8_t tima_overflow_delay;
+    int div_apu_events = 0;
+    bool write_glitches = true;
+
+    // DIV bit 4 = internal counter bit 12 (512 Hz); CGB double speed uses DIV bit 5
+    static constexpr uint8_t DIV_APU_BIT = 12;
+    uint8_t div_apu_bit = DIV_APU_BIT;
 
     static uint8_t timer_bit_for_tac(uint8_t tac);
 };
\ No newline at end of file
@@ -10,23 +10,26 @@
 #endif
 
 MMU::MMU()
-    : cartridge_type(0), rom_size_code(0), ram_size_code(0),
-      interrupt_flag(0), interrupt_enable(0),
+    : interrupt_flag(0), interrupt_enable(0),
       joypad_state(0xFF)
 {
     timer = new Timer();
     mbc = nullptr;
+    apu.set_frame_sequencer_from_div(true);
     memory.fill(0xFF);
     // Initialize VRAM area to 0x00 to avoid default black tiles caused by 0xFF.
     // Many tests rely on VRAM being clear (0) until the ROM writes tiles into VRAM.
     for (uint16_t addr = 0x8000; addr <= 0x9FFF; ++addr) {
         memory[addr] = 0x00;
     }
+#if GB_SERIAL_DEBUG
     // Open serial output file in project root
     serial_output_file.open("serial_output.txt", std::ios::out | std::ios::trunc);
+#endif
 }
 
 MMU::~MMU() {
+    link_serial(nullptr);
     delete timer;
     delete mbc;
     // Close serial output file
@@ -56,92 +59,43 @@ bool MMU::load_rom(const std::vector<uint8_t>& rom_data) {
 }
 
 void MMU::parse_rom_header() {
-    rom_title.clear();
-    for (int i = 0x0134; i <= 0x0143 && i < (int)rom.size(); ++i) {
-        char c = (char)rom[i];
-        if (c == 0) break;
-        rom_title += c;
-    }
-    if (rom.size() > 0x0149) {
-        cartridge_type = rom[0x0147];
-        rom_size_code = rom[0x0148];
-        ram_size_code = rom[0x0149];
-    }
+    // ROMs too small to hold a header are treated as ROM ONLY without external RAM
+    header = CartridgeHeader{};
+    CartridgeHeader::parse(rom, header);
 
     // Setup external RAM size
-    size_t ram_size = 0;
-    switch (ram_size_code) {
-        case 0x00: ram_size = 0; break;
-        case 0x01: ram_size = 2 * 1024; break;
-        case 0x02: ram_size = 8 * 1024; break;
-        case 0x03: ram_size = 32 * 1024; break;
-        case 0x04: ram_size = 128 * 1024; break;
-        case 0x05: ram_size = 64 * 1024; break;
-        default: ram_size = 0; break;
-    }
+    size_t ram_size = header.ram_size_bytes();
+    // MBC2 has 512 x 4-bit RAM built into the controller (header RAM size is 0)
+    if (header.cartridge_type == 0x05 || header.cartridge_type == 0x06) ram_size = 512;
     external_ram.assign(ram_size, 0x00);
 
-    // Create MBC
-    mbc = new MBC(static_cast<MBCType>(cartridge_type), rom, external_ram);
+    // Create MBC (replacing the one from a previously loaded ROM)
+    delete mbc;
+    mbc = new MBC(static_cast<MBCType>(header.cartridge_type), rom, external_ram);
+
+    // CGB 模式由卡匣標頭 0x0143 決定
+    cgb_mode = is_cgb_rom();
+    vram_bank = 0;
+    wram_bank = 0;
+    double_speed = false;
+    speed_switch_armed = false;
+    timer->set_double_speed(false);
 }
 
 std::string MMU::get_cartridge_type() const {
-    switch (cartridge_type) {
-        case 0x00: return "ROM ONLY";
-        case 0x01: return "MBC1";
-        case 0x02: return "MBC1+RAM";
-        case 0x03: return "MBC1+RAM+BATTERY";
-        case 0x05: return "MBC2";
-        case 0x06: return "MBC2+BATTERY";
-        case 0x08: return "ROM+RAM";
-        case 0x09: return "ROM+RAM+BATTERY";
-        case 0x0F: return "MBC3+TIMER+BATTERY";
-        case 0x10: return "MBC3+TIMER+RAM+BATTERY";
-        case 0x11: return "MBC3";
-        case 0x12: return "MBC3+RAM";
-        case 0x13: return "MBC3+RAM+BATTERY";
-        case 0x19: return "MBC5";
-        case 0x1A: return "MBC5+RAM";
-        case 0x1B: return "MBC5+RAM+BATTERY";
-        case 0x1C: return "MBC5+RUMBLE";
-        case 0x1D: return "MBC5+RUMBLE+RAM";
-        case 0x1E: return "MBC5+RUMBLE+RAM+BATTERY";
-        default: return "UNKNOWN";
-    }
+    return header.type_name();
 }
 
 std::string MMU::get_rom_size() const {
-    switch (rom_size_code) {
-        case 0x00: return "32KB";
-        case 0x01: return "64KB";
-        case 0x02: return "128KB";
-        case 0x03: return "256KB";
-        case 0x04: return "512KB";
-        case 0x05: return "1MB";
-        case 0x06: return "2MB";
-        case 0x07: return "4MB";
-        case 0x08: return "8MB";
-        case 0x52: return "1.1MB";
-        case 0x53: return "1.2MB";
-        case 0x54: return "1.5MB";
-        default: return "UNKNOWN";
-    }
+    return header.rom_size();
 }
 
 std::string MMU::get_ram_size() const {
-    switch (ram_size_code) {
-        case 0x00: return "None";
-        case 0x01: return "2KB";
-        case 0x02: return "8KB";
-        case 0x03: return "32KB";
-        case 0x04: return "128KB";
-        case 0x05: return "64KB";
-        default: return "UNKNOWN";
-    }
+    return header.ram_size();
 }
 
 bool MMU::has_battery() const {
-    switch (cartridge_type) {
+    switch (header.cartridge_type) {
         case 0x03: case 0x06: case 0x09: case 0x0D: case 0x0F: case 0x10:
         case 0x13: case 0x1B: case 0x1E: case 0x22: case 0xFF:
             return true;
@@ -154,6 +108,119 @@ bool MMU::is_japanese() const {
     return rom.size() > 0x014A && rom[0x014A] == 0x00;
 }
 
+bool MMU::is_cgb_rom() const {
+    if (rom.size() <= 0x0143) return false;
+    uint8_t cgb_flag = rom[0x0143];
+    return cgb_flag == 0x80 || cgb_flag == 0xC0;
+}
+
+bool MMU::supports_dmg() const {
+    return rom.size() <= 0x0143 || rom[0x0143] != 0xC0;
+}
+
+bool MMU::header_logo_valid(const std::vector<uint8_t>& rom_data) {
+    static const uint8_t NINTENDO_LOGO[48] = {
+        0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+        0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+        0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+    };
+    if (rom_data.size() < 0x0134) return false;
+    return std::equal(std::begin(NINTENDO_LOGO), std::end(NINTENDO_LOGO), rom_data.begin() + 0x0104);
+}
+
+bool MMU::header_checksum_valid(const std::vector<uint8_t>& rom_data) {
+    if (rom_data.size() <= 0x014D) return false;
+    uint8_t sum = 0;
+    for (int i = 0x0134; i <= 0x014C; ++i) sum = static_cast<uint8_t>(sum - rom_data[i] - 1);
+    return sum == rom_data[0x014D];
+}
+
+bool MMU::save_ram(const std::string& path) const {
+    if (!has_battery() || external_ram.empty()) return false;
+    std::ofstream file(path, std::ios::binary | std::ios::trunc);
+    if (!file.is_open()) return false;
+    file.write(reinterpret_cast<const char*>(external_ram.data()), static_cast<std::streamsize>(external_ram.size()));
+    return file.good();
+}
+
+bool MMU::load_ram(const std::string& path) {
+    if (!has_battery() || external_ram.empty()) return false;
+    std::ifstream file(path, std::ios::binary);
+    if (!file.is_open()) return false;
+    // 檔案較短時只覆蓋前段，較長時忽略多餘資料
+    file.read(reinterpret_cast<char*>(external_ram.data()), static_cast<std::streamsize>(external_ram.size()));
+    return file.gcount() > 0;
+}
+
+void MMU::save_state(StateWriter& w) const {
+    w.put(memory);
+    w.put(cgb_mode);
+    w.put(vram_bank);
+    w.put(vram_bank1);
+    w.put(wram_bank);
+    w.put(cgb_wram);
+    w.put(double_speed);
+    w.put(speed_switch_armed);
+    w.put(interrupt_flag);
+    w.put(interrupt_enable);
+    w.put(serial_cycles_remaining);
+    w.put(oam_bug_last_pair_base);
+    w.put(oam_bug_last_valid);
+    w.put(static_cast<uint32_t>(external_ram.size()));
+    w.put_bytes(external_ram.data(), external_ram.size());
+    w.put(get_cartridge_state());
+    timer->save_state(w);
+    ppu.save_state(w);
+    apu.save_state(w);
+}
+
+void MMU::load_state(StateReader& r) {
+    r.get(memory);
+    r.get(cgb_mode);
+    r.get(vram_bank);
+    r.get(vram_bank1);
+    r.get(wram_bank);
+    r.get(cgb_wram);
+    r.get(double_speed);
+    r.get(speed_switch_armed);
+    r.get(interrupt_flag);
+    r.get(interrupt_enable);
+    r.get(serial_cycles_remaining);
+    r.get(oam_bug_last_pair_base);
+    r.get(oam_bug_last_valid);
+    uint32_t ram_size = 0;
+    r.get(ram_size);
+    if (ram_size != external_ram.size()) {
+        r.fail(); // 存檔來自不同的卡帶
+        return;
+    }
+    r.get_bytes(external_ram.data(), external_ram.size());
+    MBC::State cart{};
+    r.get(cart);
+    set_cartridge_state(cart);
+    timer->load_state(r);
+    timer->set_double_speed(double_speed);
+    ppu.load_state(r);
+    apu.load_state(r);
+}
+
+bool MMU::try_speed_switch() {
+    if (!cgb_mode || !speed_switch_armed) return false;
+    double_speed = !double_speed;
+    speed_switch_armed = false;
+    timer->set_double_speed(double_speed);
+    return true;
+}
+
+MBC::State MMU::get_cartridge_state() const {
+    if (mbc) return mbc->get_state();
+    return MBC::State{};
+}
+
+void MMU::set_cartridge_state(const MBC::State& state) {
+    if (mbc) mbc->set_state(state);
+}
+
 // --- Memory access ---
 
 // PPU 專用讀取：繞過 Mode2/3 的 VRAM/OAM 鎖定，僅供 PPU 在渲染時讀取
@@ -166,7 +233,89 @@ uint8_t MMU::ppu_read(uint16_t address) {
     return read_byte(address);
 }
 
+// WRAM：0xC000-0xCFFF 固定 bank 0；0xD000-0xDFFF 在 CGB 模式下由 SVBK 選 bank 1-7（寫 0 視為 1）
+uint8_t& MMU::wram_at(uint16_t address) {
+    if (cgb_mode && address >= 0xD000 && wram_bank > 1) {
+        return cgb_wram[(wram_bank - 2) * 0x1000 + (address - 0xD000)];
+    }
+    return memory[address];
+}
+
+// PPU 專用：指定 VRAM bank 讀取（bank 1 僅 CGB 模式存在，存放 tile 屬性與額外 tile 資料）
+uint8_t MMU::ppu_read_vram(uint8_t bank, uint16_t address) const {
+    if (bank == 1 && cgb_mode) return vram_bank1[(address - VRAM_START) & 0x1FFF];
+    return memory[address];
+}
+
+void MMU::apply_compat_shims(const CompatShims& shims) {
+    compat_shims = shims;
+    if (compat_shims.ram_fill) {
+        std::fill(memory.begin() + WRAM_START, memory.begin() + WRAM_END + 1, compat_shims.ram_fill_value);
+        cgb_wram.fill(compat_shims.ram_fill_value);
+        std::fill(memory.begin() + HRAM_START, memory.begin() + HRAM_END + 1, compat_shims.ram_fill_value);
+    }
+}
+
+// 未對應位址（讀取時為 open bus）
+bool MMU::is_open_bus(uint16_t address) const {
+    if (address >= 0xFEA0 && address <= 0xFEFF) return true;
+    if (address >= EXTERNAL_RAM_START && address <= EXTERNAL_RAM_END) {
+        return !mbc || !mbc->ram_mapped();
+    }
+    if (address == 0xFF03 || (address >= 0xFF08 && address <= 0xFF0E)) return true;
+    if (address >= 0xFF4C && address <= IO_REGISTERS_END) {
+        if (cgb_mode && (address == 0xFF4D || address == 0xFF4F || (address >= 0xFF51 && address <= 0xFF55) ||
+                         (address >= 0xFF68 && address <= 0xFF6B) || address == 0xFF70)) {
+            return false;
+        }
+        return true;
+    }
+    return false;
+}
+
+void MMU::add_watchpoint(uint16_t address, bool on_write) {
+    (on_write ? write_watchpoints : read_watchpoints).insert(address);
+}
+
+void MMU::remove_watchpoint(uint16_t address, bool on_write) {
+    (on_write ? write_watchpoints : read_watchpoints).erase(address);
+}
+
+void MMU::clear_watchpoints() {
+    read_watchpoints.clear();
+    write_watchpoints.clear();
+    watchpoint_hit = false;
+}
+
+bool MMU::take_watchpoint_hit(uint16_t& address) {
+    if (!watchpoint_hit) return false;
+    address = watchpoint_hit_address;
+    watchpoint_hit = false;
+    return true;
+}
+
+uint8_t MMU::peek_byte(uint16_t address) {
+    if (!(compat_shims.open_bus && is_open_bus(address))) {
+        // 除錯檢視：VRAM/OAM 不受 PPU mode 鎖定
+        if (address >= VRAM_START && address <= 0x9FFF) {
+            return (cgb_mode && vram_bank == 1) ? vram_bank1[address - VRAM_START] : memory[address];
+        }
+        if (address >= 0xFE00 && address <= 0xFE9F) return memory[address];
+    }
+    bool hit = watchpoint_hit;
+    uint16_t hit_address = watchpoint_hit_address;
+    uint8_t value = read_byte(address);
+    watchpoint_hit = hit;
+    watchpoint_hit_address = hit_address;
+    return value;
+}
+
 uint8_t MMU::read_byte(uint16_t address) {
+    if (!read_watchpoints.empty() && !watchpoint_hit && read_watchpoints.count(address)) {
+        watchpoint_hit = true;
+        watchpoint_hit_address = address;
+    }
+    if (compat_shims.open_bus && is_open_bus(address)) return compat_shims.open_bus_value;
     if (address <= ROM_BANK_0_END) {
         if (address < rom.size()) return rom[address];
         return 0xFF;
@@ -177,6 +326,11 @@ uint8_t MMU::read_byte(uint16_t address) {
     } else if (address >= EXTERNAL_RAM_START && address <= EXTERNAL_RAM_END) {
         if (mbc) return mbc->get_ram_bank(address);
         return 0xFF;
+    } else if (address >= WRAM_START && address <= WRAM_END) {
+        return wram_at(address);
+    } else if (address >= ECHO_RAM_START && address <= ECHO_RAM_END) {
+        // Echo RAM mirrors 0xC000-0xDDFF
+        return wram_at(address - 0x2000);
     } else if (address == 0xFF00) {
         return get_joypad_state(memory[0xFF00]);
     } else if (address == 0xFF04) {
@@ -197,6 +351,14 @@ uint8_t MMU::read_byte(uint16_t address) {
         return interrupt_enable;
     }
 
+    // CGB-only registers: VRAM bank select and palette RAM
+    if (cgb_mode) {
+        if (address == 0xFF4F) return 0xFE | vram_bank;
+        if (address == 0xFF70) return 0xF8 | wram_bank;
+        if (address == 0xFF4D) return 0x7E | (double_speed ? 0x80 : 0x00) | (speed_switch_armed ? 0x01 : 0x00);
+        if (address >= 0xFF68 && address <= 0xFF6B) return ppu.read_cgb_palette_register(address);
+    }
+
     // PPU registers
     switch (address) {
         case 0xFF40: return ppu.get_lcdc();
@@ -221,7 +383,7 @@ uint8_t MMU::read_byte(uint16_t address) {
     }
 
     // OAM access restriction during Mode 2 (OAM search) and Mode 3 (pixel transfer)
-    if (address >= 0xFE00 && address <= 0xFE9F) {
+    if (address >= 0xFE00 && address <= 0xFE9F && accuracy == AccuracyLevel::Accurate) {
         uint8_t mode = ppu.get_stat() & 0x03;
         if (mode == 2 || mode == 3) {
             return 0xFF; // OAM locked, return 0xFF
@@ -231,21 +393,43 @@ uint8_t MMU::read_byte(uint16_t address) {
     // VRAM access restriction during Mode 3 (pixel transfer)
     if (address >= 0x8000 && address <= 0x9FFF) {
         uint8_t mode = ppu.get_stat() & 0x03;
-        if (mode == 3) {
+        if (mode == 3 && accuracy == AccuracyLevel::Accurate) {
             return 0xFF; // VRAM locked, return 0xFF
         }
+        if (cgb_mode && vram_bank == 1) return vram_bank1[address - VRAM_START];
     }
 
     return memory[address];
 }
 
 void MMU::write_byte(uint16_t address, uint8_t value) {
+    if (!write_watchpoints.empty() && !watchpoint_hit && write_watchpoints.count(address)) {
+        watchpoint_hit = true;
+        watchpoint_hit_address = address;
+    }
     if (address <= ROM_BANK_N_END) {
         // MBC writes
         if (mbc) mbc->handle_write(address, value);
         return;
     }
 
+    // External RAM goes through the MBC (bank mapping + RAM enable)
+    if (address >= EXTERNAL_RAM_START && address <= EXTERNAL_RAM_END) {
+        if (mbc) mbc->write_ram(address, value);
+        return;
+    }
+
+    if (address >= WRAM_START && address <= WRAM_END) {
+        wram_at(address) = value;
+        return;
+    }
+
+    // Echo RAM mirrors 0xC000-0xDDFF
+    if (address >= ECHO_RAM_START && address <= ECHO_RAM_END) {
+        wram_at(address - 0x2000) = value;
+        return;
+    }
+
     // Handle special registers (joypad, serial, timer, interrupts)
     if (handle_special_registers(address, value)) return;
 
@@ -270,31 +454,75 @@ void MMU::write_byte(uint16_t address, uint8_t value) {
     memory[address] = value;
 }
 
+void MMU::link_serial(MMU* peer) {
+    if (serial_peer == peer) return;
+    if (serial_peer) serial_peer->serial_peer = nullptr;
+    serial_peer = peer;
+    if (peer) {
+        if (peer->serial_peer) peer->serial_peer->serial_peer = nullptr;
+        peer->serial_peer = this;
+    }
+}
+
+void MMU::complete_serial_transfer() {
+    // 8 個位元一次移完：雙方 SB 互換
+    uint8_t sent = memory[0xFF01];
+    if (serial_peer) {
+        uint8_t received = serial_peer->memory[0xFF01];
+        serial_peer->memory[0xFF01] = sent;
+        memory[0xFF01] = received;
+        // 從機只有在等待傳輸（SC bit7=1）時才會完成並觸發中斷
+        if (serial_peer->memory[0xFF02] & 0x80) {
+            serial_peer->memory[0xFF02] &= 0x7F;
+            serial_peer->interrupt_flag |= 0x08;
+            serial_peer->notify_serial_byte(received);
+        }
+    } else {
+        memory[0xFF01] = 0xFF; // no cable: the data line floats high
+    }
+    memory[0xFF02] &= 0x7F;
+    interrupt_flag |= 0x08;
+    notify_serial_byte(sent);
+}
+
+void MMU::notify_serial_byte(uint8_t value) {
+#if GB_SERIAL_DEBUG
+    // Print test output for serial debugging (blargg ROMs report results over serial)
+    char output_char = (char)value;
+    std::cout << output_char;
+    std::cout.flush();
+    // Also write to serial output file
+    if (serial_output_file.is_open()) {
+        serial_output_file << output_char;
+        serial_output_file.flush();
+    }
+#endif
+    if (serial_callback) serial_callback(value);
+}
+
 bool MMU::handle_special_registers(uint16_t address, uint8_t value) {
     switch (address) {
         case 0xFF00:
             memory[0xFF00] = value; // joypad select bits
             return true;
-        case 0xFF01: {
+        case 0xFF01:
             memory[0xFF01] = value; // Serial data register
-#if GB_SERIAL_DEBUG
-            // Print test output for serial debugging
-            char output_char = (char)value;
-            std::cout << output_char;
-            std::cout.flush();
-            // Also write to serial output file
-            if (serial_output_file.is_open()) {
-                serial_output_file << output_char;
-                serial_output_file.flush();
-            }
-#endif
             return true;
-        }
         case 0xFF02:
             memory[0xFF02] = value; // Serial control register
+            // 內部時脈啟動傳輸（主機端）：8 個位元移完後與連線對象交換 SB，沒有連線時收到 0xFF
+            // 8192 Hz = 512 T-cycles/bit；CGB 快速模式（bit1）為 262144 Hz = 16 T-cycles/bit
+            if ((value & 0x81) == 0x81) {
+                serial_cycles_remaining = 8 * ((cgb_mode && (value & 0x02)) ? 16 : 512);
+            } else if (!(value & 0x80)) {
+                serial_cycles_remaining = 0; // clearing bit 7 aborts the transfer
+            }
             return true;
         case 0xFF04:
             timer->set_divider(0);
+            for (int n = timer->take_div_apu_events(); n > 0; --n) {
+                apu.clock_frame_sequencer();
+            }
             return true;
         case 0xFF05: // TIMA
             timer->set_timer_counter(value);
@@ -317,6 +545,12 @@ bool MMU::handle_special_registers(uint16_t address, uint8_t value) {
 }
 
 bool MMU::handle_ppu_registers(uint16_t address, uint8_t value) {
+    if (cgb_mode) {
+        if (address == 0xFF4F) { vram_bank = value & 0x01; return true; }
+        if (address == 0xFF70) { wram_bank = value & 0x07; return true; }
+        if (address == 0xFF4D) { speed_switch_armed = (value & 0x01) != 0; return true; }
+        if (address >= 0xFF68 && address <= 0xFF6B) { ppu.write_cgb_palette_register(address, value); return true; }
+    }
     switch (address) {
         case 0xFF40: ppu.set_lcdc(value); return true;
         case 0xFF41: ppu.set_stat(value); return true;
@@ -334,7 +568,7 @@ bool MMU::handle_ppu_registers(uint16_t address, uint8_t value) {
 }
 
 bool MMU::handle_oam_access(uint16_t address, uint8_t value) {
-    if (address >= 0xFE00 && address <= 0xFE9F) {
+    if (address >= 0xFE00 && address <= 0xFE9F && accuracy == AccuracyLevel::Accurate) {
         uint8_t mode = ppu.get_stat() & 0x03;
         if (mode == 2) {
             // OAM bug: During Mode 2 (OAM Search), writes to OAM are corrupted.
@@ -364,9 +598,13 @@ bool MMU::handle_oam_access(uint16_t address, uint8_t value) {
 bool MMU::handle_vram_access(uint16_t address, uint8_t value) {
     if (address >= 0x8000 && address <= 0x9FFF) {
         uint8_t mode = ppu.get_stat() & 0x03;
-        if (mode == 3) {
+        if (mode == 3 && accuracy == AccuracyLevel::Accurate) {
             return true; // VRAM locked, ignore write
         }
+        if (cgb_mode && vram_bank == 1) {
+            vram_bank1[address - VRAM_START] = value;
+            return true;
+        }
     }
     return false;
 }
@@ -384,8 +622,17 @@ bool MMU::handle_oam_dma(uint16_t address, uint8_t value) {
         for (uint16_t i = 0; i < 160; ++i) {
             uint16_t src = src_base + i;
             uint16_t dst = 0xFE00 + i;
-            // During DMA, reads should come from memory array directly (bypass restrictions)
-            uint8_t b = memory[src];
+            // DMG: 0xE000-0xFFFF 來源全部映射到 WRAM（0xFE/0xFF 頁也讀到 0xDE/0xDF）
+            if (src >= ECHO_RAM_START) src -= 0x2000;
+            // ROM / 外部 RAM 經由 MBC 解碼；VRAM/WRAM 直接讀（DMA 不受 PPU mode 鎖定）
+            uint8_t b;
+            if (src <= ROM_BANK_N_END || (src >= EXTERNAL_RAM_START && src <= EXTERNAL_RAM_END)) {
+                b = read_byte(src);
+            } else if (src >= WRAM_START) {
+                b = wram_at(src);
+            } else {
+                b = memory[src];
+            }
             memory[dst] = b;
         }
         memory[address] = value;
@@ -404,6 +651,18 @@ void MMU::update_timer_cycles(uint8_t cycles) {
     if (interrupt) {
         interrupt_flag |= 0x04;
     }
+    for (int n = timer->take_div_apu_events(); n > 0; --n) {
+        apu.clock_frame_sequencer();
+    }
+
+    // Serial shift clock (internal clock only)
+    if (serial_cycles_remaining > 0) {
+        serial_cycles_remaining -= cycles;
+        if (serial_cycles_remaining <= 0) {
+            serial_cycles_remaining = 0;
+            complete_serial_transfer();
+        }
+    }
 }
 
 // --- Joypad ---
@@ -418,18 +677,16 @@ void MMU::set_joypad_bit(int bit, bool pressed) {
 
 uint8_t MMU::get_joypad_state(uint8_t select) const {
     // Select bits: bit4=direction (0 active), bit5=buttons (0 active)
-    uint8_t result = 0xFF;
-    // Preserve upper bits
-    result = (result & 0xF0);
+    // Low nibble is active low; unselected groups read as 1, both selected are ANDed together
+    uint8_t low = 0x0F;
     if (!(select & 0x10)) { // Direction
-        result = (result & 0xF0) | (joypad_state & 0x0F);
+        low &= (joypad_state & 0x0F);
     }
     if (!(select & 0x20)) { // Buttons
-        result = (result & 0xF0) | ((joypad_state >> 4) & 0x0F);
+        low &= ((joypad_state >> 4) & 0x0F);
     }
-    // Bits 6-7 stay high
-    result |= 0xC0;
-    return result;
+    // Bits 6-7 stay high, bits 4-5 read back the select lines
+    return 0xC0 | (select & 0x30) | low;
 }
 
 
@@ -6,10 +6,45 @@
 #include <vector>
 #include <string>
 #include <fstream>
+#include <functional>
+#include <unordered_set>
 #include "ppu.h"
 #include "apu.h"
 #include "Timer.h"
 #include "MBC.h"
+#include "CartridgeHeader.h"
+
+// Workarounds for games that probe emulator quirks; everything is off by default
+struct CompatShims {
+    // open_bus: reads from unmapped addresses return open_bus_value instead of whatever the
+    // backing array holds. Covers 0xFEA0-0xFEFF, unused I/O (0xFF03, 0xFF08-0xFF0E, 0xFF4C-0xFF7F
+    // except the CGB registers in CGB mode) and 0xA000-0xBFFF while the MBC maps neither RAM
+    // nor an RTC register there. Writes are unaffected. For games that detect emulators by
+    // reading unused addresses and expecting a specific value (usually 0xFF, sometimes 0x00).
+    bool open_bus = false;
+    uint8_t open_bus_value = 0xFF;
+    // ram_fill: WRAM (all banks) and HRAM are overwritten with ram_fill_value when the shims are
+    // applied, replacing the default power-on pattern. For games that read RAM before writing it
+    // and misbehave on a particular pattern. Apply before the game starts running; it is not
+    // re-applied on reset or ROM load.
+    bool ram_fill = false;
+    uint8_t ram_fill_value = 0x00;
+};
+
+// Accuracy switch for the behaviors that have a faster/more lenient variant.
+//   Accurate (default): CPU reads of VRAM during mode 3 and OAM during modes 2/3 return 0xFF,
+//                       writes there are dropped, and OAM writes in mode 2 hit the OAM corruption bug.
+//                       DIV/TAC writes that drop the selected timer bit clock TIMA once, STAT sources
+//                       share one interrupt line (a new source is blocked while the line is high),
+//                       and HALT with IME=0 and a pending interrupt triggers the HALT bug.
+//   Fast:               VRAM and OAM are always accessible to the CPU (no locking, no corruption),
+//                       DIV/TAC writes never clock TIMA, every STAT source raises IF bit 1 on its own
+//                       rising edge, and HALT with a pending interrupt simply falls through.
+// OAM DMA copies instantly, so there is no DMA bus conflict to toggle at either level.
+enum class AccuracyLevel {
+    Fast,
+    Accurate
+};
 
 class MMU {
 public:
@@ -21,27 +56,69 @@ public:
     // Memory map
     uint8_t read_byte(uint16_t address);
     void write_byte(uint16_t address, uint8_t value);
+    // Debugger read: same value as read_byte(), except that it never reports a read watchpoint
+    // and VRAM/OAM read as stored even while the PPU mode locks them for the CPU
+    uint8_t peek_byte(uint16_t address);
 
     // ROM loading and parsing
     bool load_rom(const std::vector<uint8_t>& rom_data);
     void parse_rom_header();
 
     // ROM information
-    std::string get_title() const { return rom_title; }
+    const CartridgeHeader& get_cartridge_header() const { return header; }
+    std::string get_title() const { return header.title; }
     std::string get_cartridge_type() const;
     std::string get_rom_size() const;
+    // ROM size declared by header code 0x0148 (32KB << code, plus 0x52-0x54); 0 if unknown
+    size_t get_rom_size_bytes() const { return header.rom_size_bytes(); }
     std::string get_ram_size() const;
     bool has_battery() const;
     bool is_japanese() const;
+    // CGB flag (0x0143): 0x80 = CGB enhanced (DMG compatible), 0xC0 = CGB only
+    bool is_cgb_rom() const;
+    bool supports_dmg() const;
+    // Header integrity as the boot ROM checks it: Nintendo logo (0x0104-0x0133) and
+    // header checksum (0x014D over 0x0134-0x014C). Homebrew/patched ROMs may fail either.
+    static bool header_logo_valid(const std::vector<uint8_t>& rom_data);
+    static bool header_checksum_valid(const std::vector<uint8_t>& rom_data);
+
+    // Battery-backed external RAM persistence (.sav); no-op for carts without battery/RAM
+    bool save_ram(const std::string& path) const;
+    bool load_ram(const std::string& path);
+
+    // Flat view of all cartridge RAM banks (bank n at offset n * 0x2000; MBC2: 512 nibbles in the low 4 bits).
+    // Independent of the current bank mapping and RAM enable; do not resize.
+    std::vector<uint8_t>& get_external_ram() { return external_ram; }
+    const std::vector<uint8_t>& get_external_ram() const { return external_ram; }
+
+    // Cartridge banking state (MBC registers only, not RAM contents)
+    MBC::State get_cartridge_state() const;
+    void set_cartridge_state(const MBC::State& state);
+
+    // Save state: memory map contents, CGB banks, interrupts, serial, cartridge RAM and banking,
+    // timer, PPU and APU. Loading fails if the cartridge RAM size differs from the loaded ROM's.
+    void save_state(StateWriter& w) const;
+    void load_state(StateReader& r);
 
     // PPU access
     PPU& get_ppu() { return ppu; }
     const PPU& get_ppu() const { return ppu; }
     // PPU 專用讀取：在 Mode2/3 期間也允許讀 VRAM/OAM（僅限 PPU 自身使用）
     uint8_t ppu_read(uint16_t address);
+    uint8_t ppu_read_vram(uint8_t bank, uint16_t address) const;
+
+    // CGB mode (from header 0x0143): enables VRAM bank 1 (0xFF4F), WRAM banks 1-7 (0xFF70)
+    // and palette RAM (0xFF68-0xFF6B)
+    bool is_cgb_mode() const { return cgb_mode; }
+
+    // CGB double speed (KEY1, 0xFF4D): bit 7 reads the current speed, bit 0 the armed switch.
+    // STOP calls try_speed_switch(); it toggles the speed and returns true if a switch was armed.
+    bool is_double_speed() const { return double_speed; }
+    bool try_speed_switch();
 
     // APU access
     APU& get_apu() { return apu; }
+    const APU& get_apu() const { return apu; }
 
     // Joypad input handling
     void set_joypad_bit(int bit, bool pressed);
@@ -49,6 +126,7 @@ public:
 
     // Timer access and update
     uint8_t get_timer_control() const { return timer->get_timer_control(); }
+    // Advances the timer and the serial shift clock
     void update_timer_cycles(uint8_t cycles);
 
     // Expose current T-cycle modulo (for quick timing hacks only)
@@ -57,6 +135,34 @@ public:
     // Force-align internal cycle counter to 4T boundary (Route A hack)
     void force_align_cycle_boundary() { timer->force_align_cycle_boundary(); }
 
+    // Link cable: connect this serial port to another MMU (nullptr to disconnect).
+    // Links are symmetric; the side that starts a transfer with the internal clock
+    // (SC = 0x81) is the master and exchanges SB with the peer once its 8 bits are shifted.
+    void link_serial(MMU* peer);
+    MMU* get_serial_peer() const { return serial_peer; }
+
+    // Called with each byte this side shifts out once its transfer completes (e.g. blargg test output)
+    void set_serial_callback(std::function<void(uint8_t)> callback) { serial_callback = std::move(callback); }
+
+    // Debug watchpoints: read_byte/write_byte of a watched address records a hit,
+    // which CPU::step collects after the instruction that made the access
+    void add_watchpoint(uint16_t address, bool on_write);
+    void remove_watchpoint(uint16_t address, bool on_write);
+    void clear_watchpoints();
+    // Returns true (and the address) if a watchpoint was hit since the last call
+    bool take_watchpoint_hit(uint16_t& address);
+
+    // Compatibility shims (see CompatShims)
+    void apply_compat_shims(const CompatShims& shims);
+    const CompatShims& get_compat_shims() const { return compat_shims; }
+
+    // Accuracy level (see AccuracyLevel)
+    void set_accuracy(AccuracyLevel level) {
+        accuracy = level;
+        timer->set_write_glitches(level == AccuracyLevel::Accurate);
+    }
+    AccuracyLevel get_accuracy() const { return accuracy; }
+
 private:
     std::array<uint8_t, 0x10000> memory; // 64KB total
 
@@ -83,10 +189,7 @@ private:
 
     // ROM data
     std::vector<uint8_t> rom;
-    std::string rom_title;
-    uint8_t cartridge_type;
-    uint8_t rom_size_code;
-    uint8_t ram_size_code;
+    CartridgeHeader header;
 
     // PPU
     PPU ppu;
@@ -94,6 +197,16 @@ private:
     // APU
     APU apu;
 
+    // CGB state
+    bool cgb_mode = false;
+    uint8_t vram_bank = 0;
+    std::array<uint8_t, 0x2000> vram_bank1{};
+    uint8_t wram_bank = 0; // SVBK value as written; 0 selects bank 1
+    std::array<uint8_t, 0x1000 * 6> cgb_wram{}; // WRAM banks 2-7 (bank 1 lives in memory[])
+    bool double_speed = false;
+    bool speed_switch_armed = false;
+    uint8_t& wram_at(uint16_t address);
+
     // MBC (Memory Bank Controller) support
     MBC* mbc;
     std::vector<uint8_t> external_ram;
@@ -110,9 +223,24 @@ private:
 
     // Serial output file
     std::ofstream serial_output_file;
+    // Link cable peer (not owned)
+    MMU* serial_peer = nullptr;
+    std::function<void(uint8_t)> serial_callback;
+    int serial_cycles_remaining = 0; // T-cycles until the current internal-clock transfer completes
+    void complete_serial_transfer();
+    void notify_serial_byte(uint8_t value);
     // For debug logging: track last known CPU PC
     uint16_t last_cpu_pc = 0;
 
+    CompatShims compat_shims;
+    AccuracyLevel accuracy = AccuracyLevel::Accurate;
+    bool is_open_bus(uint16_t address) const;
+
+    std::unordered_set<uint16_t> read_watchpoints;
+    std::unordered_set<uint16_t> write_watchpoints;
+    bool watchpoint_hit = false;
+    uint16_t watchpoint_hit_address = 0;
+
     // Write byte helper functions
     bool handle_special_registers(uint16_t address, uint8_t value);
     bool handle_ppu_registers(uint16_t address, uint8_t value);
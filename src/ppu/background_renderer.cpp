@@ -29,6 +29,8 @@ void BackgroundRenderer::render_background(MMU& mmu, uint8_t ly, uint8_t lcdc, u
 
         uint16_t tile_map_addr = bg_tile_map + tile_y * 32 + tile_x;
         uint8_t tile_id = mmu.ppu_read(tile_map_addr);
+        // CGB: VRAM bank 1 holds the attribute byte for each map entry
+        uint8_t attr = mmu.is_cgb_mode() ? mmu.ppu_read_vram(1, tile_map_addr) : 0;
 
         // Handle tile ID addressing based on LCDC.4
         uint16_t tile_addr;
@@ -45,11 +47,14 @@ void BackgroundRenderer::render_background(MMU& mmu, uint8_t ly, uint8_t lcdc, u
             tile_addr = 0x9000 + (signed_id * 16);
         }
 
-        uint8_t pixel = get_tile_pixel(mmu, tile_addr, bg_x % 8, bg_y % 8);
+        // CGB attributes: bit 3 tile bank, bit 5 X flip, bit 6 Y flip
+        int px = (attr & 0x20) ? 7 - bg_x % 8 : bg_x % 8;
+        int py = (attr & 0x40) ? 7 - bg_y % 8 : bg_y % 8;
+        uint8_t pixel = get_tile_pixel(mmu, tile_addr, px, py, (attr >> 3) & 0x01);
 
-        uint32_t color = get_color(pixel, bgp);
+        uint32_t color = mmu.is_cgb_mode() ? mmu.get_ppu().get_cgb_bg_color(attr & 0x07, pixel) : get_color(pixel, bgp);
         framebuffer[ly * 160 + x] = color;
-        bgwin_pixel_ids[ly * 160 + x] = pixel; // store raw color id for priority
+        bgwin_pixel_ids[ly * 160 + x] = pixel | (attr & 0x80); // raw color id for priority, bit 7 = CGB BG priority
     }
 }
 
@@ -57,9 +62,9 @@ void BackgroundRenderer::render_window(MMU& mmu, uint8_t ly, uint8_t lcdc, uint8
                                       uint16_t win_line_counter,
                                       std::array<uint32_t, 160 * 144>& framebuffer,
                                       std::array<uint8_t, 160 * 144>& bgwin_pixel_ids) {
-    // Window appears only when LY >= WY, WX >= 7, and WX <= 166 (hardware limit)
+    // Window appears only when LCDC.5 is set, LY >= WY and WX <= 166 (hardware limit)
+    if (!(lcdc & 0x20)) return;
     if (ly < wy) return;
-    if (wx < 7) return; // Hardware: WX < 7 不顯示 window
     if (wx > 166) return; // Outside drawable range; spec: only 0-166 inclusive shows
 
     uint16_t win_tile_map = (lcdc & 0x40) ? 0x9C00 : 0x9800;
@@ -67,17 +72,18 @@ void BackgroundRenderer::render_window(MMU& mmu, uint8_t ly, uint8_t lcdc, uint8
 
     // Hardware subtracts 7 from WX to obtain left edge (WX=7 -> x=0)
     int win_x = (int)wx - 7;
-    // If window starts off-screen to the left (WX < 7) we begin drawing at x=0
+    // If window starts off-screen to the left (WX < 7) we begin drawing at x=0 and the
+    // first 7-WX window pixels are discarded (fine scroll, like SCX for the background)
     if (win_x >= 160) return; // Starts beyond right edge
 
     for (int x = std::max(0, win_x); x < 160; ++x) {
-        int win_pixel_x = x - win_x; // Window-local X (0..159)
+        int win_pixel_x = x - win_x; // Window-local X (0..165)
         // Use internal window line counter for vertical addressing, not (LY - WY)
         int win_pixel_y = static_cast<int>(win_line_counter);
 
-        // Clamp window coordinates to prevent out-of-bounds access
-        if (win_pixel_x < 0 || win_pixel_x >= 160) continue;
-        if (win_pixel_y < 0 || win_pixel_y >= 144) continue;
+        // Clamp window coordinates to the 256x256 window map
+        if (win_pixel_x < 0 || win_pixel_x >= 256) continue;
+        if (win_pixel_y < 0 || win_pixel_y >= 256) continue;
 
         int tile_x = win_pixel_x / 8;
         int tile_y = win_pixel_y / 8;
@@ -87,6 +93,7 @@ void BackgroundRenderer::render_window(MMU& mmu, uint8_t ly, uint8_t lcdc, uint8
 
         uint16_t tile_map_addr = win_tile_map + tile_y * 32 + tile_x;
         uint8_t tile_id = mmu.ppu_read(tile_map_addr);
+        uint8_t attr = mmu.is_cgb_mode() ? mmu.ppu_read_vram(1, tile_map_addr) : 0;
 
         // Handle tile ID addressing based on LCDC.4
         uint16_t tile_addr;
@@ -99,18 +106,48 @@ void BackgroundRenderer::render_window(MMU& mmu, uint8_t ly, uint8_t lcdc, uint8
             tile_addr = 0x9000 + (signed_id * 16);
         }
 
-        uint8_t pixel = get_tile_pixel(mmu, tile_addr, win_pixel_x % 8, win_pixel_y % 8);
+        int px = (attr & 0x20) ? 7 - win_pixel_x % 8 : win_pixel_x % 8;
+        int py = (attr & 0x40) ? 7 - win_pixel_y % 8 : win_pixel_y % 8;
+        uint8_t pixel = get_tile_pixel(mmu, tile_addr, px, py, (attr >> 3) & 0x01);
         // Window overwrites background unconditionally (even color 0)
-        uint32_t color = get_color(pixel, bgp);
+        uint32_t color = mmu.is_cgb_mode() ? mmu.get_ppu().get_cgb_bg_color(attr & 0x07, pixel) : get_color(pixel, bgp);
         framebuffer[ly * 160 + x] = color;
-        bgwin_pixel_ids[ly * 160 + x] = pixel; // window overwrites background id
+        bgwin_pixel_ids[ly * 160 + x] = pixel | (attr & 0x80); // window overwrites background id
     }
 }
 
-uint8_t BackgroundRenderer::get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y) const {
+std::vector<uint32_t> BackgroundRenderer::render_full_background(MMU& mmu, uint8_t lcdc, uint8_t bgp) const {
+    std::vector<uint32_t> image(256 * 256);
+    uint16_t bg_tile_map = (lcdc & 0x08) ? 0x9C00 : 0x9800;
+
+    for (int tile_y = 0; tile_y < 32; ++tile_y) {
+        for (int tile_x = 0; tile_x < 32; ++tile_x) {
+            uint8_t tile_id = mmu.ppu_read(bg_tile_map + tile_y * 32 + tile_x);
+
+            // Same LCDC.4 addressing as render_background
+            uint16_t tile_addr;
+            if (lcdc & 0x10) {
+                tile_addr = 0x8000 + (tile_id * 16);
+            } else {
+                int8_t signed_id = (int8_t)tile_id;
+                tile_addr = 0x9000 + (signed_id * 16);
+            }
+
+            for (int py = 0; py < 8; ++py) {
+                for (int px = 0; px < 8; ++px) {
+                    uint8_t pixel = get_tile_pixel(mmu, tile_addr, px, py);
+                    image[(tile_y * 8 + py) * 256 + tile_x * 8 + px] = get_color(pixel, bgp);
+                }
+            }
+        }
+    }
+    return image;
+}
+
+uint8_t BackgroundRenderer::get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y, uint8_t bank) const {
     uint16_t row_addr = tile_addr + y * 2;
-    uint8_t byte1 = mmu.ppu_read(row_addr);
-    uint8_t byte2 = mmu.ppu_read(row_addr + 1);
+    uint8_t byte1 = mmu.ppu_read_vram(bank, row_addr);
+    uint8_t byte2 = mmu.ppu_read_vram(bank, row_addr + 1);
 
     // Extract the bit for this pixel (bit 7-x)
     uint8_t bit1 = (byte1 >> (7 - x)) & 0x01;
@@ -125,12 +162,5 @@ uint32_t BackgroundRenderer::get_color(uint8_t color_id, uint8_t palette) const
     uint8_t shift = color_id * 2;
     uint8_t color_value = (palette >> shift) & 0x03;
 
-    // Game Boy color palette (simplified grayscale for background)
-    switch (color_value) {
-        case 0: return 0xFFFFFFFF; // White
-        case 1: return 0xFFAAAAAA; // Light gray
-        case 2: return 0xFF555555; // Dark gray
-        case 3: return 0xFF000000; // Black
-        default: return 0xFFFFFFFF;
-    }
+    return shades[color_value];
 }
\ No newline at end of file
@@ -54,4 +54,44 @@ void LCDController::process_pending_lcd_enable(uint64_t global_cycles, uint16_t&
 
 void LCDController::dump_lcd_on_summary() const {
     // Debug function - removed debug output
-}
\ No newline at end of file
+}
+
+void LCDController::save_state(StateWriter& w) const {
+    w.put(lcdc);
+    w.put(stat);
+    w.put(scy);
+    w.put(scx);
+    w.put(ly);
+    w.put(lyc);
+    w.put(wy);
+    w.put(wx);
+    w.put(bgp);
+    w.put(obp0);
+    w.put(obp1);
+    w.put(win_line_counter);
+    w.put(lcd_was_on);
+    w.put(static_cast<int64_t>(off_cycle_counter));
+    w.put(pending_lcd_enable);
+    w.put(pending_lcd_enable_delay);
+}
+
+void LCDController::load_state(StateReader& r) {
+    r.get(lcdc);
+    r.get(stat);
+    r.get(scy);
+    r.get(scx);
+    r.get(ly);
+    r.get(lyc);
+    r.get(wy);
+    r.get(wx);
+    r.get(bgp);
+    r.get(obp0);
+    r.get(obp1);
+    r.get(win_line_counter);
+    r.get(lcd_was_on);
+    int64_t off_cycles = 0;
+    r.get(off_cycles);
+    off_cycle_counter = static_cast<long>(off_cycles);
+    r.get(pending_lcd_enable);
+    r.get(pending_lcd_enable_delay);
+}
@@ -6,6 +6,9 @@
 PPU::PPU() : cycle_count(0), shadow_scx(0), shadow_scy(0), ppu_mode(0) {
     framebuffer.fill(0xFFFFFFFF); // White background (will be overwritten per scanline)
     bgwin_pixel_ids.fill(0);      // All background pixels start as color 0
+    cgb_bg_palette_ram.fill(0xFF); // CGB boot leaves BG palettes white
+    cgb_obj_palette_ram.fill(0xFF);
+    set_palette(DEFAULT_PALETTE);
 
     // OAM bug scan tracking init
     oam_search_pair_base = 0xFE00;
@@ -29,6 +32,8 @@ void PPU::step(int cycles, MMU& mmu) {
             ppu_mode = 0; // 靜止狀態視作 mode 0
             lcd_controller.set_stat((lcd_controller.get_stat() & ~0x03) | 0x00);
             lcd_controller.increment_off_cycle_counter(); // 保留偏移
+            stat_line = false;
+            stat_sources = 0;
             continue; // 不執行渲染或中斷
         }
         // Determine mode based on whether we're in VBlank or visible area
@@ -53,14 +58,8 @@ void PPU::step(int cycles, MMU& mmu) {
             ppu_mode = new_mode;
             lcd_controller.set_stat((lcd_controller.get_stat() & ~0x03) | ppu_mode);
 
-            if (ppu_mode == 2) {
-                // Mode 2 (OAM Search)
-                if (lcd_controller.get_stat() & 0x20) {
-                    uint8_t if_reg = mmu.read_byte(0xFF0F);
-                    if_reg |= 0x02;
-                    mmu.write_byte(0xFF0F, if_reg);
-                }
-            } else if (ppu_mode == 3) {
+            // STAT mode 0/1/2 interrupts are raised by update_stat_line()
+            if (ppu_mode == 3) {
                 // Mode 3 (Pixel Transfer) - snapshot scroll values and render
                 shadow_scx = lcd_controller.get_scx();
                 shadow_scy = lcd_controller.get_scy();
@@ -76,26 +75,12 @@ void PPU::step(int cycles, MMU& mmu) {
                         ev.mode3_recorded = true;
                     }
                 }
-            } else if (ppu_mode == 0) {
-                // Mode 0 (HBlank)
-                if (lcd_controller.get_stat() & 0x08) {
-                    uint8_t if_reg = mmu.read_byte(0xFF0F);
-                    if_reg |= 0x02;
-                    mmu.write_byte(0xFF0F, if_reg);
-                }
             } else if (ppu_mode == 1) {
                 // Mode 1 (VBlank) - triggered at LY=144
                 if (lcd_controller.get_ly() == 144) {
                     uint8_t if_reg = mmu.read_byte(0xFF0F);
                     if_reg |= 0x01; // VBlank interrupt
                     mmu.write_byte(0xFF0F, if_reg);
-
-                    // STAT mode 1 interrupt
-                    if (lcd_controller.get_stat() & 0x10) {
-                        if_reg = mmu.read_byte(0xFF0F);
-                        if_reg |= 0x02;
-                        mmu.write_byte(0xFF0F, if_reg);
-                    }
                 }
             }
         }
@@ -130,22 +115,10 @@ void PPU::step(int cycles, MMU& mmu) {
             // Window line counter increments only on lines where the window is actually visible
             // Conditions: window enabled, in visible area, LY >= WY, WX <= 166
             // We check the line that just finished (old_ly), so the increment applies for next line's rendering
-            if ((lcd_controller.get_lcdc() & 0x20) && old_ly < 144 && old_ly >= lcd_controller.get_wy() && lcd_controller.get_wx() <= 166) {
+            if (window_enabled(mmu) && old_ly < 144 && old_ly >= lcd_controller.get_wy() && lcd_controller.get_wx() <= 166) {
                 lcd_controller.increment_win_line_counter();
             }
 
-            // LYC coincidence
-            if (lcd_controller.get_ly() == lcd_controller.get_lyc()) {
-                lcd_controller.set_stat(lcd_controller.get_stat() | 0x04);
-                if (lcd_controller.get_stat() & 0x40) {
-                    uint8_t if_reg = mmu.read_byte(0xFF0F);
-                    if_reg |= 0x02;
-                    mmu.write_byte(0xFF0F, if_reg);
-                }
-            } else {
-                lcd_controller.set_stat(lcd_controller.get_stat() & ~0x04);
-            }
-
             // Frame reset when LY reaches 154
             if (lcd_controller.get_ly() == 154) {
                 lcd_controller.set_ly(0);
@@ -154,15 +127,68 @@ void PPU::step(int cycles, MMU& mmu) {
 
                 // Reset window line counter at start of new frame
                 lcd_controller.set_win_line_counter(0);
-
-                // STAT mode 2 interrupt
-                if (lcd_controller.get_stat() & 0x20) {
-                    uint8_t if_reg = mmu.read_byte(0xFF0F);
-                    if_reg |= 0x02;
-                    mmu.write_byte(0xFF0F, if_reg);
-                }
             }
+
+            // LYC coincidence (after the frame wrap so LYC=0 matches line 0)
+            update_lyc_coincidence();
         }
+
+        update_stat_line(mmu);
+    }
+}
+
+void PPU::update_lyc_coincidence() {
+    if (lcd_controller.get_ly() == lcd_controller.get_lyc()) {
+        lcd_controller.set_stat(lcd_controller.get_stat() | 0x04);
+    } else {
+        lcd_controller.set_stat(lcd_controller.get_stat() & ~0x04);
+    }
+}
+
+uint8_t PPU::active_stat_sources() const {
+    uint8_t stat = lcd_controller.get_stat();
+    return (((stat & 0x40) && (stat & 0x04)) ? 0x08 : 0) |
+           (((stat & 0x20) && ppu_mode == 2) ? 0x04 : 0) |
+           (((stat & 0x10) && ppu_mode == 1) ? 0x02 : 0) |
+           (((stat & 0x08) && ppu_mode == 0) ? 0x01 : 0);
+}
+
+void PPU::update_stat_line(MMU& mmu) {
+    // 四個 STAT 來源 OR 成一條中斷線，只在上升緣觸發 IF bit1（STAT blocking：線已為高時新來源不再觸發）
+    // AccuracyLevel::Fast 不做 blocking：每個來源在自己的上升緣各自觸發
+    uint8_t sources = active_stat_sources();
+    bool line = sources != 0;
+    bool fire = mmu.get_accuracy() == AccuracyLevel::Accurate ? (line && !stat_line)
+                                                             : (sources & ~stat_sources) != 0;
+    if (fire) {
+        uint8_t if_reg = mmu.read_byte(0xFF0F);
+        if_reg |= 0x02;
+        mmu.write_byte(0xFF0F, if_reg);
+    }
+    stat_line = line;
+    stat_sources = sources;
+}
+
+void PPU::set_stat(uint8_t value) {
+    // CPU 只能寫入 bit3-6（中斷來源選擇）；mode 與 LYC 比對旗標由 PPU 維護
+    lcd_controller.set_stat((value & 0x78) | (lcd_controller.get_stat() & 0x07));
+}
+
+void PPU::set_lyc(uint8_t value) {
+    lcd_controller.set_lyc(value);
+    if (lcd_controller.get_lcdc() & 0x80) update_lyc_coincidence();
+}
+
+void PPU::set_lcdc(uint8_t value) {
+    bool turning_off = (lcd_controller.get_lcdc() & 0x80) && !(value & 0x80);
+    lcd_controller.set_lcdc(value);
+    if (turning_off) {
+        // LCD 關閉立即生效：LY=0、STAT mode=0，掃描線計時歸零，重新開啟時從第 0 行開始
+        lcd_controller.set_ly(0);
+        ppu_mode = 0;
+        lcd_controller.set_stat(lcd_controller.get_stat() & ~0x03);
+        cycle_count = 0;
+        lcd_controller.set_win_line_counter(0);
     }
 }
 
@@ -177,23 +203,156 @@ void PPU::render_scanline(MMU& mmu, uint8_t shadow_scx, uint8_t shadow_scy) {
         bgwin_pixel_ids[lcd_controller.get_ly() * 160 + x] = 0; // raw color id 0
     }
 
-    if (lcd_controller.get_lcdc() & 0x01) background_renderer.render_background(mmu, lcd_controller.get_ly(), lcd_controller.get_lcdc(), shadow_scx, shadow_scy, lcd_controller.get_bgp(), framebuffer, bgwin_pixel_ids);
-    if (lcd_controller.get_lcdc() & 0x20) background_renderer.render_window(mmu, lcd_controller.get_ly(), lcd_controller.get_lcdc(), lcd_controller.get_wy(), lcd_controller.get_wx(), lcd_controller.get_bgp(), lcd_controller.get_win_line_counter(), framebuffer, bgwin_pixel_ids);
+    // CGB: LCDC.0 is the BG/window master priority instead of a BG enable, so the BG is always drawn
+    if ((lcd_controller.get_lcdc() & 0x01) || mmu.is_cgb_mode()) background_renderer.render_background(mmu, lcd_controller.get_ly(), lcd_controller.get_lcdc(), shadow_scx, shadow_scy, lcd_controller.get_bgp(), framebuffer, bgwin_pixel_ids);
+    if (window_enabled(mmu)) background_renderer.render_window(mmu, lcd_controller.get_ly(), lcd_controller.get_lcdc(), lcd_controller.get_wy(), lcd_controller.get_wx(), lcd_controller.get_bgp(), lcd_controller.get_win_line_counter(), framebuffer, bgwin_pixel_ids);
     if (lcd_controller.get_lcdc() & 0x02) sprite_renderer.render_sprites(mmu, lcd_controller.get_ly(), lcd_controller.get_lcdc(), lcd_controller.get_obp0(), lcd_controller.get_obp1(), framebuffer, bgwin_pixel_ids);
 }
 
+bool PPU::window_enabled(const MMU& mmu) const {
+    uint8_t lcdc = lcd_controller.get_lcdc();
+    if (!(lcdc & 0x20)) return false;
+    return (lcdc & 0x01) || mmu.is_cgb_mode();
+}
+
 uint32_t PPU::get_color(uint8_t color_id, uint8_t palette) const {
-    // GameBoy color palette (simplified)
-    // 調亮 GameBoy 綠色調色盤
-    static const uint32_t colors[4] = {
-        0xFFFFFFFF, // White (最亮)
-        0xFFCADFAA, // Slightly lighter green
-        0xFF8EBF60, // Light green
-        0xFF305030  // Dark green (darker for contrast tests)
+    uint8_t shade = (palette >> (color_id * 2)) & 0x03;
+    return shades[shade];
+}
+
+uint8_t PPU::read_cgb_palette_register(uint16_t address) const {
+    switch (address) {
+        case 0xFF68: return bcps | 0x40; // bit 6 unused, reads 1
+        case 0xFF69: return cgb_bg_palette_ram[bcps & 0x3F];
+        case 0xFF6A: return ocps | 0x40;
+        case 0xFF6B: return cgb_obj_palette_ram[ocps & 0x3F];
+        default: return 0xFF;
+    }
+}
+
+void PPU::write_cgb_palette_register(uint16_t address, uint8_t value) {
+    switch (address) {
+        case 0xFF68: bcps = value & 0xBF; break;
+        case 0xFF69:
+            cgb_bg_palette_ram[bcps & 0x3F] = value;
+            if (bcps & 0x80) bcps = 0x80 | ((bcps + 1) & 0x3F); // auto-increment wraps within 64 bytes
+            break;
+        case 0xFF6A: ocps = value & 0xBF; break;
+        case 0xFF6B:
+            cgb_obj_palette_ram[ocps & 0x3F] = value;
+            if (ocps & 0x80) ocps = 0x80 | ((ocps + 1) & 0x3F);
+            break;
+        default: break;
+    }
+}
+
+uint32_t PPU::get_cgb_bg_color(uint8_t palette, uint8_t color_id) const {
+    // 每個顏色 2 bytes（little-endian BGR555）
+    size_t index = (palette & 0x07) * 8 + (color_id & 0x03) * 2;
+    return cgb_color_to_argb(static_cast<uint16_t>(cgb_bg_palette_ram[index] | (cgb_bg_palette_ram[index + 1] << 8)));
+}
+
+uint32_t PPU::get_cgb_obj_color(uint8_t palette, uint8_t color_id) const {
+    size_t index = (palette & 0x07) * 8 + (color_id & 0x03) * 2;
+    return cgb_color_to_argb(static_cast<uint16_t>(cgb_obj_palette_ram[index] | (cgb_obj_palette_ram[index + 1] << 8)));
+}
+
+void PPU::save_state(StateWriter& w) const {
+    w.put(framebuffer);
+    w.put(bgwin_pixel_ids);
+    w.put(cycle_count);
+    w.put(ppu_mode);
+    w.put(shadow_scx);
+    w.put(shadow_scy);
+    w.put(global_cycles);
+    w.put(stat_line);
+    w.put(oam_search_pair_base);
+    w.put(oam_last_mode2_pair_base);
+    w.put(cgb_bg_palette_ram);
+    w.put(cgb_obj_palette_ram);
+    w.put(bcps);
+    w.put(ocps);
+    lcd_controller.save_state(w);
+}
+
+void PPU::load_state(StateReader& r) {
+    r.get(framebuffer);
+    r.get(bgwin_pixel_ids);
+    r.get(cycle_count);
+    r.get(ppu_mode);
+    r.get(shadow_scx);
+    r.get(shadow_scy);
+    r.get(global_cycles);
+    r.get(stat_line);
+    r.get(oam_search_pair_base);
+    r.get(oam_last_mode2_pair_base);
+    r.get(cgb_bg_palette_ram);
+    r.get(cgb_obj_palette_ram);
+    r.get(bcps);
+    r.get(ocps);
+    lcd_controller.load_state(r);
+    // Not saved: rebuilt from STAT and the mode so Fast does not re-fire a source already active
+    stat_sources = stat_line ? active_stat_sources() : 0;
+}
+
+void PPU::set_palette(const std::array<uint32_t, 4>& colors) {
+    // 背景、視窗、sprite 共用同一組 DMG 顏色
+    shades = colors;
+    background_renderer.set_shades(colors);
+    sprite_renderer.set_shades(colors);
+}
+
+std::vector<uint8_t> PPU::export_frame_2bpp() const {
+    std::vector<uint8_t> out(20 * 18 * 16, 0);
+    auto shade_index = [this](uint32_t argb) {
+        // 找最接近的 shade（自訂調色盤或 CGB 顏色也能對應）
+        int best = 0;
+        int best_dist = -1;
+        for (int i = 0; i < 4; ++i) {
+            int dr = static_cast<int>((argb >> 16) & 0xFF) - static_cast<int>((shades[i] >> 16) & 0xFF);
+            int dg = static_cast<int>((argb >> 8) & 0xFF) - static_cast<int>((shades[i] >> 8) & 0xFF);
+            int db = static_cast<int>(argb & 0xFF) - static_cast<int>(shades[i] & 0xFF);
+            int dist = dr * dr + dg * dg + db * db;
+            if (best_dist < 0 || dist < best_dist) {
+                best = i;
+                best_dist = dist;
+            }
+        }
+        return static_cast<uint8_t>(best);
     };
+    // BGP 反查：shade -> 最小的 color index（沒有對應時保留 shade 本身）
+    std::array<uint8_t, 4> color_for_shade = {0, 1, 2, 3};
+    uint8_t bgp = lcd_controller.get_bgp();
+    for (int color = 3; color >= 0; --color) color_for_shade[(bgp >> (color * 2)) & 0x03] = static_cast<uint8_t>(color);
+    for (int y = 0; y < 144; ++y) {
+        for (int x = 0; x < 160; ++x) {
+            uint8_t id = color_for_shade[shade_index(framebuffer[y * 160 + x])];
+            size_t tile = (y / 8) * 20 + (x / 8);
+            size_t row = tile * 16 + (y % 8) * 2;
+            uint8_t bit = static_cast<uint8_t>(0x80 >> (x % 8));
+            if (id & 0x01) out[row] |= bit;      // low bit plane
+            if (id & 0x02) out[row + 1] |= bit;  // high bit plane
+        }
+    }
+    return out;
+}
 
-    uint8_t shade = (palette >> (color_id * 2)) & 0x03;
-    return colors[shade];
+uint32_t PPU::cgb_color_to_argb(uint16_t bgr555) const {
+    uint32_t r = bgr555 & 0x1F;
+    uint32_t g = (bgr555 >> 5) & 0x1F;
+    uint32_t b = (bgr555 >> 10) & 0x1F;
+    uint32_t out_r, out_g, out_b;
+    if (color_correction == ColorCorrection::Gambatte) {
+        // 模擬 CGB 面板的色彩混合（偏淡、紅綠互滲），每個通道最大 248
+        out_r = (r * 13 + g * 2 + b) >> 1;
+        out_g = (g * 3 + b) << 1;
+        out_b = (r * 3 + g * 2 + b * 11) >> 1;
+    } else {
+        out_r = (r << 3) | (r >> 2);
+        out_g = (g << 3) | (g >> 2);
+        out_b = (b << 3) | (b >> 2);
+    }
+    return 0xFF000000 | (out_r << 16) | (out_g << 8) | out_b;
 }
 
 // Register access functions
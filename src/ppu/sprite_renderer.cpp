@@ -14,19 +14,24 @@ void SpriteRenderer::render_sprites(MMU& mmu, uint8_t ly, uint8_t lcdc, uint8_t
     // Sprite rendering (OAM search and rendering)
     // GameBoy supports up to 40 sprites, but only 10 per scanline
 
-    auto sprites_on_line = collect_sprites_on_line(mmu, ly, lcdc);
+    auto sprites_on_line = select_sprites_on_line(mmu, ly, lcdc);
     if (!sprites_on_line.empty()) {
-        // 正確排序：X 較小優先，X 相同時 OAM index 較小優先
-        std::sort(sprites_on_line.begin(), sprites_on_line.end(), [](const Sprite& a, const Sprite& b) {
-            if (a.x == b.x) return a.oam_index < b.oam_index;
-            return a.x < b.x;
-        });
         render_sprite_pixels(mmu, sprites_on_line, ly, lcdc, obp0, obp1, framebuffer, bgwin_pixel_ids);
     }
 
     // TODO: OAM/VRAM locking: 在 mode 2/3 時，MMU 應禁止 CPU 存取 OAM/VRAM 區域
 }
 
+std::vector<SpriteRenderer::Sprite> SpriteRenderer::select_sprites_on_line(MMU& mmu, uint8_t ly, uint8_t lcdc) const {
+    auto sprites_on_line = collect_sprites_on_line(mmu, ly, lcdc);
+    // 正確排序：X 較小優先，X 相同時 OAM index 較小優先（CGB 只看 OAM index，收集順序即是）
+    if (!mmu.is_cgb_mode()) std::sort(sprites_on_line.begin(), sprites_on_line.end(), [](const Sprite& a, const Sprite& b) {
+        if (a.x == b.x) return a.oam_index < b.oam_index;
+        return a.x < b.x;
+    });
+    return sprites_on_line;
+}
+
 std::vector<SpriteRenderer::Sprite> SpriteRenderer::collect_sprites_on_line(MMU& mmu, uint8_t ly, uint8_t lcdc) const {
     std::vector<Sprite> sprites_on_line;
     uint8_t sprite_height_global = (lcdc & 0x04) ? 16 : 8;
@@ -68,6 +73,8 @@ void SpriteRenderer::render_sprite_pixels(MMU& mmu, const std::vector<Sprite>& s
         bool behind_bg;
         uint8_t palette;
         int start_x;
+        uint8_t bank;        // CGB: attribute bit 3
+        uint8_t cgb_palette; // CGB: attribute bits 0-2
     };
 
     std::vector<SpriteEval> evals;
@@ -103,11 +110,17 @@ void SpriteRenderer::render_sprite_pixels(MMU& mmu, const std::vector<Sprite>& s
             xflip,
             behind_bg,
             palette,
-            static_cast<int>(sprite.x) - 8  // start_x (adjusted)
+            static_cast<int>(sprite.x) - 8,  // start_x (adjusted)
+            static_cast<uint8_t>((sprite.attributes >> 3) & 0x01),
+            static_cast<uint8_t>(sprite.attributes & 0x07)
         });
     }
 
-    // Render pixels: process sprites in OAM order (collected order)
+    bool cgb = mmu.is_cgb_mode();
+    // CGB: LCDC.0 clear means sprites are always drawn above BG/window
+    bool bg_priority_enabled = !cgb || (lcdc & 0x01);
+
+    // Render pixels: sprites are pre-sorted by X then OAM index, first opaque pixel wins
     for (int screen_x = 0; screen_x < 160; ++screen_x) {
         for (const auto& eval : evals) {
             // 檢查 sprite 是否覆蓋此像素
@@ -117,27 +130,31 @@ void SpriteRenderer::render_sprite_pixels(MMU& mmu, const std::vector<Sprite>& s
             int px_in_sprite = screen_x - eval.start_x;
             int px = eval.xflip ? (7 - px_in_sprite) : px_in_sprite;
             uint8_t pixel = get_tile_pixel(mmu, eval.tile_addr, static_cast<uint8_t>(px),
-                                         static_cast<uint8_t>(eval.row_in_tile));
+                                         static_cast<uint8_t>(eval.row_in_tile), eval.bank);
 
             // 只有 sprite 像素非 0 才能覆蓋
             if (pixel == 0) continue;
 
             // Sprite 優先權：behind_bg 僅在背景 tile/pixel id 為 0 時才顯示
-            if (eval.behind_bg && bgwin_pixel_ids[ly * 160 + screen_x] != 0) {
-                continue;
+            // 此像素仍屬於這個（優先權最高的）sprite，被背景蓋住時不能讓後面的 sprite 透出來
+            // CGB 另有 BG 屬性 bit 7（存在 bgwin_pixel_ids 的 bit 7）可強制背景在上
+            uint8_t bg_id = bgwin_pixel_ids[ly * 160 + screen_x];
+            if (bg_priority_enabled && (bg_id & 0x03) != 0 && (eval.behind_bg || (bg_id & 0x80))) {
+                break;
             }
 
             // 這個 sprite 贏了，繪製並跳出
-            framebuffer[ly * 160 + screen_x] = get_color(pixel, eval.palette);
+            framebuffer[ly * 160 + screen_x] = cgb ? mmu.get_ppu().get_cgb_obj_color(eval.cgb_palette, pixel)
+                                                   : get_color(pixel, eval.palette);
             break;
         }
     }
 }
 
-uint8_t SpriteRenderer::get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y) const {
+uint8_t SpriteRenderer::get_tile_pixel(MMU& mmu, uint16_t tile_addr, uint8_t x, uint8_t y, uint8_t bank) const {
     uint16_t row_addr = tile_addr + y * 2;
-    uint8_t byte1 = mmu.ppu_read(row_addr);
-    uint8_t byte2 = mmu.ppu_read(row_addr + 1);
+    uint8_t byte1 = mmu.ppu_read_vram(bank, row_addr);
+    uint8_t byte2 = mmu.ppu_read_vram(bank, row_addr + 1);
 
     uint8_t bit = 7 - x;
     uint8_t pixel = ((byte1 & (1 << bit)) ? 1 : 0) | ((byte2 & (1 << bit)) ? 2 : 0);
@@ -150,12 +167,6 @@ uint32_t SpriteRenderer::get_color(uint8_t color_id, uint8_t palette) const {
     uint8_t shift = color_id * 2;
     uint8_t color_value = (palette >> shift) & 0x03;
 
-    // Game Boy color palette (simplified grayscale for sprites)
-    switch (color_value) {
-        case 0: return 0xFFFFFFFF; // White (transparent for sprites, but we handle transparency elsewhere)
-        case 1: return 0xFFAAAAAA; // Light gray
-        case 2: return 0xFF555555; // Dark gray
-        case 3: return 0xFF000000; // Black
-        default: return 0xFFFFFFFF;
-    }
+    // Color 0 is transparent for sprites, but that is handled by the caller
+    return shades[color_value];
 }
\ No newline at end of file
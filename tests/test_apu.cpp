@@ -0,0 +1,296 @@
+#include "test_helpers.h"
+#include "emulator.h"
+#include <cmath>
+
+static std::string write_idle_rom(const std::string& name) {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x18, 0xFE}); // JR -2
+    return write_rom_file(name, rom);
+}
+
+// The 512 Hz frame sequencer follows DIV, so length counters tick every 2 sequencer steps (256 Hz)
+static void test_frame_sequencer_from_div() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("apu_idle.gb")));
+    APU& apu = emulator.debug_get_apu();
+    emulator.debug_write_io(0xFF26, 0x80);
+    emulator.debug_write_io(0xFF16, 64 - 6); // NR21: CH2 length 6
+    emulator.debug_write_io(0xFF17, 0xF0);   // NR22: DAC on
+    emulator.debug_write_io(0xFF19, 0xC0);   // NR24: trigger, length enabled
+    assert(apu.debug_get_length_counter(2) == 6);
+    assert(emulator.debug_read_io(0xFF26) & 0x02);
+
+    std::vector<uint64_t> decrements;
+    int sequencer_steps = 0;
+    int last_step = apu.debug_get_frame_step();
+    int last_length = apu.debug_get_length_counter(2);
+    while (decrements.size() < 4) {
+        emulator.step();
+        if (apu.debug_get_frame_step() != last_step) {
+            last_step = apu.debug_get_frame_step();
+            ++sequencer_steps;
+        }
+        if (apu.debug_get_length_counter(2) != last_length) {
+            assert(apu.debug_get_length_counter(2) == last_length - 1);
+            last_length = apu.debug_get_length_counter(2);
+            decrements.push_back(emulator.emulated_cycles());
+        }
+        assert(emulator.emulated_cycles() < 4194304);
+    }
+    for (size_t i = 1; i < decrements.size(); ++i) {
+        uint64_t interval = decrements[i] - decrements[i - 1];
+        assert(interval >= 16384 - 16 && interval <= 16384 + 16); // 2 DIV-APU ticks of 8192 T-cycles
+    }
+    assert(sequencer_steps >= 6 && sequencer_steps <= 8);
+
+    // Writing DIV restarts the divider, delaying the next sequencer step
+    uint64_t start = emulator.emulated_cycles();
+    last_step = apu.debug_get_frame_step();
+    for (int i = 0; i < 8; ++i) {
+        emulator.debug_write_io(0xFF04, 0x00);
+        for (int k = 0; k < 200; ++k) emulator.step();
+    }
+    assert(emulator.emulated_cycles() - start > 8192);
+    assert(apu.debug_get_frame_step() == last_step);
+
+    // Length runs out: the channel turns off
+    while (apu.debug_get_length_counter(2) > 0) {
+        emulator.step();
+        assert(emulator.emulated_cycles() < 4194304);
+    }
+    assert((emulator.debug_read_io(0xFF26) & 0x02) == 0);
+}
+
+// CH1 with period 1750 plays 131072/(2048-1750) Hz; channels that aren't playing report 0
+static void test_channel_frequencies() {
+    APU apu;
+    apu.write_register(NR52, 0x80);
+    apu.write_register(NR12, 0xF0);
+    apu.write_register(NR13, 1750 & 0xFF);
+    apu.write_register(NR14, 0x80 | (1750 >> 8));
+    std::array<float, 4> freqs = apu.channel_frequencies();
+    assert(std::abs(freqs[0] - 131072.0f / (2048 - 1750)) < 0.01f);
+    assert(freqs[1] == 0.0f && freqs[2] == 0.0f && freqs[3] == 0.0f);
+
+    apu.write_register(NR12, 0x00);                 // DAC off silences CH1
+    assert(apu.channel_frequencies()[0] == 0.0f);
+}
+
+// Runs the APU long enough to produce frames stereo samples and returns them interleaved L/R
+static std::vector<float> render_stereo(APU& apu, int frames) {
+    int64_t cycles = int64_t{frames} * 4194304 / apu.get_audio_config().sample_rate + 8;
+    for (int64_t i = 0; i < cycles; i += 4) apu.step(4);
+    std::vector<float> out(frames * 2);
+    apu.get_audio_samples_stereo(out.data(), frames);
+    return out;
+}
+
+// CH1 at full volume, 50% duty, routed to both sides
+static void start_ch1(APU& apu) {
+    apu.write_register(NR52, 0x80);
+    apu.write_register(NR11, 0x80);
+    apu.write_register(NR12, 0xF0);
+    apu.write_register(NR13, 0x00);
+    apu.write_register(NR14, 0x87);
+}
+
+// A muted channel contributes nothing to the mix; master_gain scales every output sample
+static void test_apply_audio_config() {
+    APU reference;
+    start_ch1(reference);
+    std::vector<float> full = render_stereo(reference, 1000);
+    assert(std::any_of(full.begin(), full.end(), [](float s) { return s != 0.0f; }));
+
+    AudioConfig muted_config;
+    muted_config.channel_enabled[0] = false;
+    APU muted;
+    muted.apply_audio_config(muted_config);
+    start_ch1(muted);
+    std::vector<float> silent = render_stereo(muted, 1000);
+    assert(std::all_of(silent.begin(), silent.end(), [](float s) { return s == 0.0f; }));
+
+    AudioConfig half_config;
+    half_config.master_gain = 0.5f;
+    APU half;
+    half.apply_audio_config(half_config);
+    start_ch1(half);
+    std::vector<float> scaled = render_stereo(half, 1000);
+    for (size_t i = 0; i < full.size(); ++i) assert(std::abs(scaled[i] - full[i] * 0.5f) < 1e-6f);
+}
+
+// Number of times the left output crosses upward through the midpoint of its range
+static int rising_edges_left(const std::vector<float>& stereo) {
+    float low = stereo[0], high = stereo[0];
+    for (size_t i = 0; i < stereo.size(); i += 2) {
+        low = std::min(low, stereo[i]);
+        high = std::max(high, stereo[i]);
+    }
+    float mid = (low + high) / 2;
+    int edges = 0;
+    for (size_t i = 2; i < stereo.size(); i += 2) {
+        if (stereo[i - 2] < mid && stereo[i] >= mid) ++edges;
+    }
+    return edges;
+}
+
+// CH3 plays wave RAM at the rate NR33/NR34 hold when it is triggered: 65536/(2048-x) Hz
+static void test_wave_channel_period_on_trigger() {
+    APU apu;
+    apu.write_register(NR52, 0x80);
+    for (uint16_t address = 0xFF30; address < 0xFF40; ++address) {
+        apu.write_register(address, address < 0xFF38 ? 0xFF : 0x00);  // one square cycle per 32 nibbles
+    }
+    apu.write_register(NR30, 0x80);
+    apu.write_register(NR32, 0x20);                                 // 100% volume
+    for (uint16_t period : {1900, 1752}) {
+        apu.write_register(NR33, period & 0xFF);
+        apu.write_register(NR34, 0x80 | (period >> 8));
+        float hz = 65536.0f / (2048 - period);
+        assert(std::abs(apu.channel_frequencies()[2] - hz) < 0.01f);
+        render_stereo(apu, 100);                                    // let the new period settle
+        int edges = rising_edges_left(render_stereo(apu, 4410));    // 0.1 s
+        assert(std::abs(edges - hz / 10) <= 2);
+    }
+}
+
+// Left-side output flips (either direction) over the samples
+static int left_transitions(const std::vector<float>& stereo) {
+    int transitions = 0;
+    for (size_t i = 2; i < stereo.size(); i += 2) {
+        if ((stereo[i - 2] > 0) != (stereo[i] > 0)) ++transitions;
+    }
+    return transitions;
+}
+
+static float left_peak(const std::vector<float>& stereo) {
+    float peak = 0.0f;
+    for (size_t i = 0; i < stereo.size(); i += 2) peak = std::max(peak, std::abs(stereo[i]));
+    return peak;
+}
+
+// CH4's LFSR is clocked every (divisor << shift) T-cycles: a 7-bit LFSR flips its output
+// 64 times per 127 clocks, so doubling the divisor or adding one to the shift halves the flips
+static void test_noise_lfsr_clock_and_envelope() {
+    auto flips_per_half_second = [](uint8_t nr43) {
+        APU apu;
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR42, 0xF0);
+        apu.write_register(NR43, nr43 | 0x08);                       // 7-bit width
+        apu.write_register(NR44, 0x80);
+        return left_transitions(render_stereo(apu, 22050));
+    };
+    int base = flips_per_half_second(0x42);                          // shift 4, divisor code 2 (32): 8192 Hz
+    float expected = 262144.0f / (2 << 4) / 2 * 64 / 127;
+    assert(std::abs(base - expected) < expected / 10);
+    for (uint8_t slower : {0x52, 0x44}) {                            // shift 5, or divisor code 4 (64)
+        float ratio = static_cast<float>(base) / flips_per_half_second(slower);
+        assert(ratio > 1.8f && ratio < 2.2f);
+    }
+
+    // NR42 bit 3 picks the envelope direction (64 Hz steps)
+    APU rising;
+    rising.write_register(NR52, 0x80);
+    rising.write_register(NR42, 0x09);                               // volume 0, increase, period 1
+    rising.write_register(NR43, 0x42);
+    rising.write_register(NR44, 0x80);
+    float early = left_peak(render_stereo(rising, 2205));
+    render_stereo(rising, 8820);
+    assert(left_peak(render_stereo(rising, 2205)) > early);
+
+    APU falling;
+    falling.write_register(NR52, 0x80);
+    falling.write_register(NR42, 0xF1);                              // volume 15, decrease, period 1
+    falling.write_register(NR43, 0x42);
+    falling.write_register(NR44, 0x80);
+    early = left_peak(render_stereo(falling, 2205));
+    render_stereo(falling, 8820);
+    assert(left_peak(render_stereo(falling, 2205)) < early);
+}
+
+// NR51 = 0x20 sends CH2 to the left only: the right side stays silent. NR50 scales each side.
+static void test_nr51_routes_ch2_left() {
+    auto start_ch2 = [](APU& apu, uint8_t nr50) {
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR50, nr50);
+        apu.write_register(NR51, 0x20);
+        apu.write_register(NR21, 0x80);
+        apu.write_register(NR22, 0xF0);
+        apu.write_register(NR23, 0x00);
+        apu.write_register(NR24, 0x87);
+    };
+    APU loud;
+    start_ch2(loud, 0x77);
+    std::vector<float> samples = render_stereo(loud, 1000);
+    bool left_active = false;
+    for (size_t i = 0; i < samples.size(); i += 2) {
+        left_active |= samples[i] != 0.0f;
+        assert(samples[i + 1] == 0.0f);
+    }
+    assert(left_active);
+
+    APU quiet;
+    start_ch2(quiet, 0x37);                                          // left volume 3 of 7
+    std::vector<float> quieter = render_stereo(quiet, 1000);
+    for (size_t i = 0; i < samples.size(); i += 2) assert(std::abs(quieter[i] * 2 - samples[i]) < 1e-6f);
+}
+
+// NR11 bits 6-7 pick the duty: 12.5% (0) and 50% (2) of the samples are high
+static void test_nr11_duty() {
+    auto high_fraction = [](uint8_t nr11) {
+        APU apu;
+        apu.write_register(NR52, 0x80);
+        apu.write_register(NR11, nr11);
+        apu.write_register(NR12, 0xF0);
+        apu.write_register(NR13, 0x00);
+        apu.write_register(NR14, 0x84);                              // period 1024: 128 Hz
+        std::vector<float> samples = render_stereo(apu, 44100);
+        int high = 0;
+        for (size_t i = 0; i < samples.size(); i += 2) high += samples[i] > 0.0f;
+        return high / 44100.0f;
+    };
+    assert(std::abs(high_fraction(0x00) - 0.125f) < 0.01f);
+    assert(std::abs(high_fraction(0x80) - 0.5f) < 0.01f);
+}
+
+// While CH3 plays, wave RAM reads 0xFF and writes are dropped; turning the NR30 DAC off unlocks it,
+// and the rewritten table plays on the next trigger
+static void test_wave_ram_locked_while_ch3_plays() {
+    APU apu;
+    apu.write_register(NR52, 0x80);
+    for (uint16_t address = 0xFF30; address < 0xFF40; ++address) apu.write_register(address, 0x00);
+    assert(apu.read_register(0xFF30) == 0x00);
+    apu.write_register(NR30, 0x80);
+    apu.write_register(NR32, 0x20);
+    apu.write_register(NR33, 1900 & 0xFF);
+    apu.write_register(NR34, 0x80 | (1900 >> 8));
+    assert(apu.read_register(NR52) & 0x04);
+    assert(apu.read_register(0xFF30) == 0xFF);
+    apu.write_register(0xFF30, 0x12);                                // dropped
+    std::vector<float> flat = render_stereo(apu, 1000);
+    assert(rising_edges_left(flat) == 0);                            // all-zero table: constant output
+
+    apu.write_register(NR30, 0x00);
+    assert((apu.read_register(NR52) & 0x04) == 0);
+    assert(apu.read_register(0xFF30) == 0x00);
+    for (uint16_t address = 0xFF30; address < 0xFF40; ++address) {
+        apu.write_register(address, address < 0xFF38 ? 0xFF : 0x00);
+    }
+    assert(apu.read_register(0xFF30) == 0xFF && apu.read_register(0xFF38) == 0x00);
+    apu.write_register(NR30, 0x80);
+    apu.write_register(NR34, 0x80 | (1900 >> 8));
+    assert(rising_edges_left(render_stereo(apu, 4410)) > 30);       // ~443 Hz square
+}
+
+int main() {
+    test_frame_sequencer_from_div();
+    test_channel_frequencies();
+    test_apply_audio_config();
+    test_wave_channel_period_on_trigger();
+    test_noise_lfsr_clock_and_envelope();
+    test_nr51_routes_ch2_left();
+    test_nr11_duty();
+    test_wave_ram_locked_while_ch3_plays();
+    std::printf("test_apu: all tests passed\n");
+    return 0;
+}
@@ -0,0 +1,82 @@
+#include "test_helpers.h"
+#include "CartridgeHeader.h"
+
+// Writes an ASCII title at 0x0134
+static void put_title(std::vector<uint8_t>& rom, const std::string& title) {
+    for (size_t i = 0; i < title.size(); ++i) rom[0x0134 + i] = static_cast<uint8_t>(title[i]);
+}
+
+static void test_parse_header() {
+    std::vector<uint8_t> rom = make_rom(0x13, 0x05, 0x03);           // MBC3+RAM+BATTERY, 1 MB, 32 KB RAM
+    put_title(rom, "POCKETMON");
+    rom[0x014B] = 0x01;                                                // old licensee code
+    CartridgeHeader header;
+    assert(CartridgeHeader::parse(rom, header));
+    assert(header.title == "POCKETMON");
+    assert(header.cgb_flag == 0x00 && !header.is_cgb());
+    assert(header.cartridge_type == 0x13);
+    assert(header.type_name() == "MBC3+RAM+BATTERY");
+    assert(header.rom_size() == "1MB");
+    assert(header.ram_size() == "32KB");
+    assert(header.licensee == "01");
+
+    // CGB flag: the title stops at 0x0142; 0x33 switches to the two-character new licensee code
+    put_title(rom, "ABCDEFGHIJKLMNO");
+    rom[0x0143] = 0xC0;
+    rom[0x0144] = '0';
+    rom[0x0145] = '8';
+    rom[0x014B] = 0x33;
+    assert(CartridgeHeader::parse(rom, header));
+    assert(header.title == "ABCDEFGHIJKLMNO");
+    assert(header.cgb_flag == 0xC0 && header.is_cgb());
+    assert(header.licensee == "08");
+
+    // Too short to hold a header: rejected, the output is left alone
+    std::vector<uint8_t> truncated(rom.begin(), rom.begin() + 0x014F);
+    CartridgeHeader untouched;
+    untouched.title = "KEEP";
+    assert(!CartridgeHeader::parse(truncated, untouched));
+    assert(untouched.title == "KEEP");
+}
+
+// ROM size code -> bytes: 32 KB << code for 0x00-0x08, and the odd 72/80/96-bank codes
+static void test_rom_size_bytes() {
+    CartridgeHeader header;
+    header.rom_size_code = 0x00;
+    assert(header.rom_size_bytes() == 32768);
+    header.rom_size_code = 0x05;
+    assert(header.rom_size_bytes() == 1048576);
+    header.rom_size_code = 0x08;
+    assert(header.rom_size_bytes() == 8388608);
+    header.rom_size_code = 0x52;
+    assert(header.rom_size_bytes() == 72 * 16384);
+    header.rom_size_code = 0x54;
+    assert(header.rom_size_bytes() == 96 * 16384);
+    header.rom_size_code = 0x09;
+    assert(header.rom_size_bytes() == 0);
+}
+
+// Header byte 0x0143: 0x80 = CGB enhanced (DMG still fine), 0xC0 = CGB only, 0x00 = DMG
+static void test_is_cgb_rom() {
+    const struct { uint8_t flag; bool cgb; bool dmg; } cases[] = {
+        {0x80, true, true},
+        {0xC0, true, false},
+        {0x00, false, true},
+    };
+    for (const auto& c : cases) {
+        std::vector<uint8_t> rom = make_rom();
+        rom[0x0143] = c.flag;
+        MMU mmu;
+        mmu.load_rom(rom);
+        assert(mmu.is_cgb_rom() == c.cgb);
+        assert(mmu.supports_dmg() == c.dmg);
+    }
+}
+
+int main() {
+    test_parse_header();
+    test_rom_size_bytes();
+    test_is_cgb_rom();
+    std::printf("test_cartridge: all tests passed\n");
+    return 0;
+}
@@ -0,0 +1,489 @@
+#include "test_helpers.h"
+#include <sstream>
+
+// Bare CPU + MMU with a blank ROM; test programs run from WRAM with interrupts off
+struct TestMachine {
+    MMU mmu;
+    CPU cpu{mmu};
+    TestMachine() {
+        mmu.load_rom(make_rom());
+        cpu.ime = false;
+    }
+    // Execute count instructions; returns the T-cycles of the last one
+    int run(int count) {
+        int cycles = 0;
+        for (int i = 0; i < count; ++i) cycles = cpu.step();
+        return cycles;
+    }
+};
+
+static void test_adc_sbc_with_carry_in() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x37, 0x3E, 0x0E, 0x06, 0x01, 0x88}); // SCF ; LD A,$0E ; LD B,$01 ; ADC A,B
+    m.run(4);
+    assert(m.cpu.A == 0x10);
+    assert(m.cpu.half_carry_flag && !m.cpu.carry_flag && !m.cpu.subtract_flag && !m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x37, 0x3E, 0xFF, 0xCE, 0x00});       // SCF ; LD A,$FF ; ADC A,$00
+    m.run(3);
+    assert(m.cpu.A == 0x00);
+    assert(m.cpu.zero_flag && m.cpu.half_carry_flag && m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x37, 0x3E, 0x10, 0x06, 0x01, 0x98}); // SCF ; LD A,$10 ; LD B,$01 ; SBC A,B
+    m.run(4);
+    assert(m.cpu.A == 0x0E);
+    assert(m.cpu.subtract_flag && m.cpu.half_carry_flag && !m.cpu.carry_flag && !m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x37, 0x3E, 0x00, 0xDE, 0x00});       // SCF ; LD A,$00 ; SBC A,$00
+    m.run(3);
+    assert(m.cpu.A == 0xFF);
+    assert(m.cpu.carry_flag && m.cpu.half_carry_flag);
+}
+
+static void test_inc_dec_r_flags() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x37, 0x06, 0x0F, 0x04}); // SCF ; LD B,$0F ; INC B
+    m.run(3);
+    assert(m.cpu.B == 0x10);
+    assert(m.cpu.half_carry_flag && !m.cpu.subtract_flag && !m.cpu.zero_flag && m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x05});                   // DEC B (low nibble 0 -> borrow)
+    m.run(1);
+    assert(m.cpu.B == 0x0F);
+    assert(m.cpu.half_carry_flag && m.cpu.subtract_flag && !m.cpu.zero_flag && m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x3F, 0x06, 0x01, 0x05}); // CCF ; LD B,$01 ; DEC B
+    m.run(3);
+    assert(m.cpu.B == 0x00);
+    assert(m.cpu.zero_flag && m.cpu.subtract_flag && !m.cpu.half_carry_flag && !m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x06, 0xFF, 0x04});       // LD B,$FF ; INC B
+    m.run(2);
+    assert(m.cpu.B == 0x00);
+    assert(m.cpu.zero_flag && m.cpu.half_carry_flag && !m.cpu.carry_flag);
+}
+
+static void test_add_hl_rr() {
+    TestMachine m;
+    // XOR A sets Z, which ADD HL,rr must leave alone
+    load_program(m.mmu, m.cpu, {0xAF, 0x21, 0x23, 0x8A, 0x01, 0x05, 0x06, 0x09}); // XOR A ; LD HL,$8A23 ; LD BC,$0605 ; ADD HL,BC
+    m.run(4);
+    assert(m.cpu.HL == 0x9028);
+    assert(m.cpu.half_carry_flag && !m.cpu.carry_flag && !m.cpu.subtract_flag && m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x21, 0x23, 0x8A, 0x29});                         // LD HL,$8A23 ; ADD HL,HL
+    m.run(2);
+    assert(m.cpu.HL == 0x1446);
+    assert(m.cpu.half_carry_flag && m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x21, 0x00, 0xF0, 0x11, 0x00, 0x10, 0x19});       // LD HL,$F000 ; LD DE,$1000 ; ADD HL,DE
+    m.run(3);
+    assert(m.cpu.HL == 0x0000);
+    assert(!m.cpu.half_carry_flag && m.cpu.carry_flag && m.cpu.zero_flag);
+}
+
+// H and C come from the unsigned add of the low bytes, whatever the sign of the offset
+static void test_add_sp_e8() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0xAF, 0x31, 0xFF, 0x00, 0xE8, 0x01}); // XOR A ; LD SP,$00FF ; ADD SP,+1
+    assert(m.run(3) == 16);
+    assert(m.cpu.SP == 0x0100);
+    assert(m.cpu.half_carry_flag && m.cpu.carry_flag && !m.cpu.zero_flag && !m.cpu.subtract_flag);
+
+    load_program(m.mmu, m.cpu, {0x31, 0x00, 0x01, 0xE8, 0xFF});       // LD SP,$0100 ; ADD SP,-1
+    m.run(2);
+    assert(m.cpu.SP == 0x00FF);
+    assert(!m.cpu.half_carry_flag && !m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x31, 0x05, 0xD0, 0xE8, 0xFF});       // LD SP,$D005 ; ADD SP,-1
+    m.run(2);
+    assert(m.cpu.SP == 0xD004);
+    assert(m.cpu.half_carry_flag && m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x31, 0xF8, 0xFF, 0xF8, 0x10});       // LD SP,$FFF8 ; LD HL,SP+$10
+    assert(m.run(2) == 12);
+    assert(m.cpu.HL == 0x0008);
+    assert(!m.cpu.half_carry_flag && m.cpu.carry_flag);
+}
+
+static void test_inc_dec_hl_indirect() {
+    TestMachine m;
+    m.mmu.write_byte(0xC100, 0x0F);
+    load_program(m.mmu, m.cpu, {0x21, 0x00, 0xC1, 0x37, 0x34}); // LD HL,$C100 ; SCF ; INC (HL)
+    assert(m.run(3) == 12);
+    assert(m.mmu.read_byte(0xC100) == 0x10);
+    assert(m.cpu.carry_flag && m.cpu.half_carry_flag && !m.cpu.subtract_flag && !m.cpu.zero_flag);
+
+    m.mmu.write_byte(0xC100, 0x01);
+    load_program(m.mmu, m.cpu, {0x35});                         // DEC (HL)
+    assert(m.run(1) == 12);
+    assert(m.mmu.read_byte(0xC100) == 0x00);
+    assert(m.cpu.carry_flag && m.cpu.zero_flag && m.cpu.subtract_flag && !m.cpu.half_carry_flag);
+}
+
+// The accumulator rotates always clear Z, unlike their CB-prefixed versions
+static void test_accumulator_rotates_clear_z() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0xAF, 0x07});             // XOR A ; RLCA
+    m.run(2);
+    assert(m.cpu.A == 0x00 && !m.cpu.zero_flag && !m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0xAF, 0xCB, 0x07});       // XOR A ; RLC A
+    m.run(2);
+    assert(m.cpu.A == 0x00 && m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x80, 0xB7, 0x17}); // LD A,$80 ; OR A (C=0) ; RLA
+    m.run(3);
+    assert(m.cpu.A == 0x00 && m.cpu.carry_flag && !m.cpu.zero_flag);
+    assert(!m.cpu.half_carry_flag && !m.cpu.subtract_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x01, 0x0F});       // LD A,$01 ; RRCA
+    m.run(2);
+    assert(m.cpu.A == 0x80 && m.cpu.carry_flag && !m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x01, 0xB7, 0x1F}); // LD A,$01 ; OR A ; RRA
+    m.run(3);
+    assert(m.cpu.A == 0x00 && m.cpu.carry_flag && !m.cpu.zero_flag);
+}
+
+static void test_jp_hl() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x21, 0x34, 0x12, 0xE9}); // LD HL,$1234 ; JP (HL)
+    m.run(1);
+    m.cpu.add_watchpoint(0x1234, false);                   // JP (HL) must not read memory at HL
+    assert(m.run(1) == 4);
+    assert(m.cpu.PC == 0x1234);
+    assert(m.cpu.stop_reason() == CPU::StopReason::None);
+}
+
+// Loads, ALU, branches, CB and stack opcodes all dispatched through CPU::step
+static void test_small_program() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x06, 0x0A,             // LD B,10
+                                0xAF,                   // XOR A
+                                0x80,                   // loop: ADD A,B
+                                0x05,                   // DEC B
+                                0x20, 0xFC,             // JR NZ,loop
+                                0xEA, 0x00, 0xC2,       // LD ($C200),A
+                                0x21, 0x00, 0xC2,       // LD HL,$C200
+                                0x4E,                   // LD C,(HL)
+                                0xCB, 0x31,             // SWAP C
+                                0xC5,                   // PUSH BC
+                                0xD1});                 // POP DE
+    m.cpu.SP = 0xDFFE;
+    const uint16_t end = 0xC012;
+    for (int i = 0; i < 100 && m.cpu.PC != end; ++i) m.cpu.step();
+    assert(m.cpu.PC == end);
+    assert(m.cpu.A == 55);
+    assert(m.mmu.read_byte(0xC200) == 55);
+    assert(m.cpu.C == 0x73);
+    assert(m.cpu.DE == 0x0073);
+    assert(m.cpu.SP == 0xDFFE);
+}
+
+static void test_cb_prefix_consumes_two_bytes() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x3E, 0xF1, 0xCB, 0x37, 0x00}); // LD A,$F1 ; SWAP A ; NOP
+    m.run(1);
+    assert(m.run(1) == 8);
+    assert(m.cpu.PC == 0xC004);
+    assert(m.cpu.A == 0x1F);
+    assert(!m.cpu.zero_flag && !m.cpu.carry_flag);
+}
+
+static void test_add_hl_sp_half_carry_bit_11() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x21, 0x00, 0x08, 0x31, 0x00, 0x08, 0x39}); // LD HL,$0800 ; LD SP,$0800 ; ADD HL,SP
+    assert(m.run(3) == 8);
+    assert(m.cpu.HL == 0x1000);
+    assert(m.cpu.half_carry_flag && !m.cpu.carry_flag && !m.cpu.subtract_flag);
+
+    load_program(m.mmu, m.cpu, {0x21, 0x0F, 0x00, 0x31, 0x01, 0x00, 0x39}); // carry out of bit 3 only: no H
+    m.run(3);
+    assert(m.cpu.HL == 0x0010);
+    assert(!m.cpu.half_carry_flag && !m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x21, 0x00, 0xF8, 0x31, 0x00, 0x08, 0x39}); // bits 11 and 15 both carry
+    m.run(3);
+    assert(m.cpu.HL == 0x0000);
+    assert(m.cpu.half_carry_flag && m.cpu.carry_flag);
+}
+
+static void test_cb_hl_cycles() {
+    TestMachine m;
+    m.mmu.write_byte(0xC100, 0x00);
+    load_program(m.mmu, m.cpu, {0x21, 0x00, 0xC1,  // LD HL,$C100
+                                0xCB, 0xDE,        // SET 3,(HL)
+                                0xCB, 0x5E,        // BIT 3,(HL)
+                                0xCB, 0x9E,        // RES 3,(HL)
+                                0xCB, 0x5E,        // BIT 3,(HL)
+                                0xCB, 0x06});      // RLC (HL)
+    m.run(1);
+    assert(m.run(1) == 16);
+    assert(m.mmu.read_byte(0xC100) == 0x08);
+    assert(m.run(1) == 12);
+    assert(!m.cpu.zero_flag);
+    assert(m.run(1) == 16);
+    assert(m.mmu.read_byte(0xC100) == 0x00);
+    assert(m.run(1) == 12);
+    assert(m.cpu.zero_flag);
+    assert(m.run(1) == 16);
+}
+
+static void test_daa_after_add() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x3E, 0x90, 0xC6, 0x0A, 0x27}); // LD A,$90 ; ADD A,$0A (= $9A) ; DAA
+    m.run(3);
+    assert(m.cpu.A == 0x00);
+    assert(m.cpu.carry_flag && m.cpu.zero_flag && !m.cpu.subtract_flag && !m.cpu.half_carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x15, 0xC6, 0x27, 0x27}); // 15 + 27 = 42 (half carry)
+    m.run(3);
+    assert(m.cpu.A == 0x42 && !m.cpu.carry_flag && !m.cpu.zero_flag);
+}
+
+// After a subtraction DAA only applies the -0x06/-0x60 corrections
+static void test_daa_after_sub() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x3E, 0x00, 0xD6, 0x01, 0x27}); // LD A,$00 ; SUB $01 ; DAA
+    m.run(3);
+    assert(m.cpu.A == 0x99);
+    assert(m.cpu.carry_flag && m.cpu.subtract_flag && !m.cpu.half_carry_flag && !m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x10, 0xD6, 0x01, 0x27}); // 10 - 01 = 09
+    m.run(3);
+    assert(m.cpu.A == 0x09 && !m.cpu.carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x45, 0xD6, 0x45, 0x27}); // 45 - 45 = 00
+    m.run(3);
+    assert(m.cpu.A == 0x00 && m.cpu.zero_flag && !m.cpu.carry_flag);
+}
+
+// CP sets every flag like SUB but keeps A
+static void test_cp_flags() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x3E, 0x10, 0x06, 0x20, 0xB8}); // LD A,$10 ; LD B,$20 ; CP B
+    m.run(3);
+    assert(m.cpu.A == 0x10);
+    assert(m.cpu.carry_flag && !m.cpu.zero_flag && m.cpu.subtract_flag && !m.cpu.half_carry_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x10, 0xFE, 0x01}); // LD A,$10 ; CP $01: borrow from bit 4
+    m.run(2);
+    assert(m.cpu.half_carry_flag && !m.cpu.carry_flag && !m.cpu.zero_flag);
+
+    load_program(m.mmu, m.cpu, {0x3E, 0x42, 0xFE, 0x42}); // LD A,$42 ; CP $42
+    m.run(2);
+    assert(m.cpu.zero_flag && !m.cpu.carry_flag && !m.cpu.half_carry_flag && m.cpu.subtract_flag);
+}
+
+static void test_cpl_scf_ccf() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0xAF, 0x3E, 0x35, 0x2F}); // XOR A (Z=1, C=0) ; LD A,$35 ; CPL
+    m.run(3);
+    assert(m.cpu.A == 0xCA);
+    assert(m.cpu.subtract_flag && m.cpu.half_carry_flag);
+    assert(m.cpu.zero_flag && !m.cpu.carry_flag);                // Z and C untouched
+
+    // SCF: C=1, N=H=0, Z kept
+    load_program(m.mmu, m.cpu, {0x2F, 0x37});                     // CPL (N=H=1) ; SCF
+    m.run(2);
+    assert(m.cpu.carry_flag && !m.cpu.subtract_flag && !m.cpu.half_carry_flag && m.cpu.zero_flag);
+
+    // CCF: C inverted, N=H=0, Z kept
+    load_program(m.mmu, m.cpu, {0x2F, 0x3F});                     // CPL ; CCF
+    m.run(2);
+    assert(!m.cpu.carry_flag && !m.cpu.subtract_flag && !m.cpu.half_carry_flag && m.cpu.zero_flag);
+    load_program(m.mmu, m.cpu, {0x3F});                           // CCF again
+    m.run(1);
+    assert(m.cpu.carry_flag);
+}
+
+// Dispatch after an instruction: push PC, jump to the vector, clear IF and IME, 20 extra T-cycles
+static void test_interrupt_dispatch() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x00});                           // NOP
+    m.cpu.SP = 0xDFF0;
+    m.cpu.ime = true;
+    m.mmu.write_byte(0xFFFF, 0x01);
+    m.mmu.write_byte(0xFF0F, 0x01);                               // VBlank
+    assert(m.run(1) == 4 + 20);
+    assert(m.cpu.PC == 0x0040);
+    assert(m.cpu.SP == 0xDFEE);
+    assert(m.mmu.read_byte(0xDFEE) == 0x01 && m.mmu.read_byte(0xDFEF) == 0xC0); // return to $C001
+    assert(!m.cpu.ime);
+    assert((m.mmu.read_byte(0xFF0F) & 0x01) == 0);
+}
+
+// EI takes effect after the following instruction: a pending interrupt is taken after the NOP, not before
+static void test_ei_delay() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0xFB, 0x00, 0x00});               // EI ; NOP ; NOP
+    m.cpu.SP = 0xDFF0;
+    m.mmu.write_byte(0xFFFF, 0x04);
+    m.mmu.write_byte(0xFF0F, 0x04);                               // timer interrupt already pending
+    assert(m.run(1) == 4);                                        // EI
+    assert(m.cpu.PC == 0xC001 && !m.cpu.ime);
+    assert(m.run(1) == 4 + 20);                                   // NOP runs, then dispatch
+    assert(m.cpu.PC == 0x0050);
+    assert(m.mmu.read_byte(0xDFEE) == 0x02 && m.mmu.read_byte(0xDFEF) == 0xC0); // returns after the NOP
+}
+
+static void test_halt_wake() {
+    // IME=1: the wake-up step services the interrupt at once (1 M-cycle wake + 5 M-cycle dispatch)
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x76, 0x00});                     // HALT ; NOP
+    m.cpu.SP = 0xDFF0;
+    m.cpu.ime = true;
+    m.mmu.write_byte(0xFFFF, 0x04);
+    m.mmu.write_byte(0xFF0F, 0x00);
+    m.run(1);
+    assert(m.cpu.halted);
+    assert(m.run(1) == 4 && m.cpu.halted);                        // nothing pending: stays halted
+    m.mmu.write_byte(0xFF0F, 0x04);
+    assert(m.run(1) == 24);
+    assert(!m.cpu.halted && m.cpu.PC == 0x0050);
+    assert(m.mmu.read_byte(0xDFEE) == 0x01 && m.mmu.read_byte(0xDFEF) == 0xC0); // returns to the NOP
+
+    // IME=0: HALT ends without dispatch and execution continues after it
+    TestMachine n;
+    load_program(n.mmu, n.cpu, {0x76, 0x3C});                     // HALT ; INC A
+    n.cpu.A = 0x00;
+    n.mmu.write_byte(0xFFFF, 0x04);
+    n.mmu.write_byte(0xFF0F, 0x00);
+    n.run(1);
+    assert(n.cpu.halted);
+    n.mmu.write_byte(0xFF0F, 0x04);
+    n.run(1);
+    assert(!n.cpu.halted && n.cpu.A == 0x01 && n.cpu.PC == 0xC002);
+    assert(n.mmu.read_byte(0xFF0F) & 0x04);                       // still requested
+}
+
+// HALT bug: HALT with IME=0 and an interrupt pending doesn't halt, and the next byte is fetched twice
+static void test_halt_bug() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x76, 0x3C, 0x00});               // HALT ; INC A ; NOP
+    m.cpu.A = 0x00;
+    m.mmu.write_byte(0xFFFF, 0x04);
+    m.mmu.write_byte(0xFF0F, 0x04);
+    m.run(1);
+    assert(!m.cpu.halted && m.cpu.PC == 0xC001);
+    m.run(1);                                                     // INC A, PC not advanced
+    assert(m.cpu.A == 0x01 && m.cpu.PC == 0xC001);
+    m.run(1);                                                     // INC A again
+    assert(m.cpu.A == 0x02 && m.cpu.PC == 0xC002);
+}
+
+// Only IE/IF bits 0-4 are interrupt sources: bits 5-7 set in both neither dispatch nor wake HALT
+static void test_upper_interrupt_bits_ignored() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x00, 0x76, 0x00});               // NOP ; HALT ; NOP
+    m.cpu.ime = true;
+    m.mmu.write_byte(0xFFFF, 0xFF);
+    m.mmu.write_byte(0xFF0F, 0xE0);
+    assert(m.run(1) == 4 && m.cpu.PC == 0xC001);                  // no dispatch
+    m.run(1);
+    assert(m.cpu.halted);
+    for (int i = 0; i < 4; ++i) assert(m.run(1) == 4 && m.cpu.halted);
+
+    // IME=0: no HALT bug either, HALT really halts
+    TestMachine n;
+    load_program(n.mmu, n.cpu, {0x76, 0x00});
+    n.mmu.write_byte(0xFFFF, 0xFF);
+    n.mmu.write_byte(0xFF0F, 0xE0);
+    n.run(1);
+    assert(n.cpu.halted && n.cpu.PC == 0xC001);
+}
+
+// STOP resets the divider like a write to DIV
+static void test_stop_resets_div() {
+    TestMachine m;
+    for (int i = 0; i < 10; ++i) m.mmu.update_timer_cycles(200);
+    assert(m.mmu.read_byte(0xFF04) != 0);
+    load_program(m.mmu, m.cpu, {0x10, 0x00});                     // STOP
+    m.run(1);
+    assert(m.mmu.read_byte(0xFF04) == 0);
+    assert(m.cpu.PC == 0xC002);
+}
+
+// PUSH stores the high byte at SP+1 and the low byte at SP; POP reads them back
+static void test_push_pop_byte_order() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x01, 0xCD, 0xAB, 0xC5, 0xD1});   // LD BC,$ABCD ; PUSH BC ; POP DE
+    m.cpu.SP = 0xDFF0;
+    m.run(2);
+    assert(m.cpu.SP == 0xDFEE);
+    assert(m.mmu.read_byte(0xDFEF) == 0xAB);
+    assert(m.mmu.read_byte(0xDFEE) == 0xCD);
+    m.run(1);
+    assert(m.cpu.DE == 0xABCD && m.cpu.SP == 0xDFF0);
+}
+
+static void test_disassemble() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x3E, 0x42,                       // $C000: LD A,$42
+                                0xCB, 0x7C,                       // $C002: BIT 7,H
+                                0x18, 0xFA,                       // $C004: JR -6 -> $C000
+                                0x20, 0xFE});                     // $C006: JR NZ,-2 -> $C006
+    assert(CPU::disassemble(m.mmu, 0xC000) == std::make_pair(std::string("LD A,$42"), uint8_t{2}));
+    assert(CPU::disassemble(m.mmu, 0xC002) == std::make_pair(std::string("BIT 7,H"), uint8_t{2}));
+    assert(CPU::disassemble(m.mmu, 0xC004) == std::make_pair(std::string("JR $C000"), uint8_t{2}));
+    assert(CPU::disassemble(m.mmu, 0xC006) == std::make_pair(std::string("JR NZ,$C006"), uint8_t{2}));
+}
+
+// One gameboy-doctor line per executed instruction, state taken before it runs
+static void test_trace_sink() {
+    TestMachine m;
+    load_program(m.mmu, m.cpu, {0x00, 0xC3, 0x13, 0x02});         // NOP ; JP $0213
+    m.cpu.A = 0x01;
+    m.cpu.zero_flag = m.cpu.half_carry_flag = m.cpu.carry_flag = true;
+    m.cpu.subtract_flag = false;
+    m.cpu.BC = 0x0013;
+    m.cpu.DE = 0x00D8;
+    m.cpu.HL = 0x014D;
+    m.cpu.SP = 0xFFFE;
+    m.mmu.add_watchpoint(0xC002, false);                          // PCMEM reads must not trigger it
+
+    std::ostringstream trace;
+    m.cpu.set_trace_sink(&trace);
+    m.run(1);
+    const std::string expected = "A:01 F:B0 B:00 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:C000 PCMEM:00,C3,13,02\n";
+    assert(trace.str() == expected);
+    assert(m.cpu.stop_reason() == CPU::StopReason::None);
+    uint16_t hit_address = 0;
+    m.cpu.trace_line();
+    assert(!m.mmu.take_watchpoint_hit(hit_address));
+
+    m.cpu.set_trace_sink(nullptr);
+    m.mmu.remove_watchpoint(0xC002, false);
+    m.run(1);                                                     // JP: not traced
+    assert(trace.str() == expected);
+}
+
+int main() {
+    test_adc_sbc_with_carry_in();
+    test_inc_dec_r_flags();
+    test_add_hl_rr();
+    test_add_sp_e8();
+    test_inc_dec_hl_indirect();
+    test_accumulator_rotates_clear_z();
+    test_jp_hl();
+    test_small_program();
+    test_cb_prefix_consumes_two_bytes();
+    test_add_hl_sp_half_carry_bit_11();
+    test_cb_hl_cycles();
+    test_daa_after_add();
+    test_daa_after_sub();
+    test_cp_flags();
+    test_cpl_scf_ccf();
+    test_interrupt_dispatch();
+    test_ei_delay();
+    test_halt_wake();
+    test_halt_bug();
+    test_upper_interrupt_bits_ignored();
+    test_stop_resets_div();
+    test_push_pop_byte_order();
+    test_disassemble();
+    test_trace_sink();
+    std::printf("test_cpu: all tests passed\n");
+    return 0;
+}
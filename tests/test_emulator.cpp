@@ -0,0 +1,527 @@
+#include "test_helpers.h"
+#include "emulator.h"
+#include <sstream>
+#include <cmath>
+#include <algorithm>
+
+// Timer interrupt + a loop storing TIMA into WRAM, so a state captures CPU, timer, interrupts and RAM
+static std::string write_timer_loop_rom(const std::string& name) {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0050, {0xD9});                                // timer vector: RETI
+    put_code(rom, 0x0150, {0x3E, 0x05, 0xE0, 0x07,                // LD A,$05 ; LDH (TAC),A
+                           0x3E, 0x04, 0xE0, 0xFF,                // LD A,$04 ; LDH (IE),A
+                           0xFB,                                  // EI
+                           0x21, 0x00, 0xC1,                      // LD HL,$C100
+                           0xF0, 0x05,                            // loop: LDH A,(TIMA)
+                           0x22,                                  // LD (HL+),A
+                           0x7C,                                  // LD A,H
+                           0xFE, 0xD0,                            // CP $D0
+                           0x20, 0xF8,                            // JR NZ,loop
+                           0x26, 0xC1,                            // LD H,$C1
+                           0x18, 0xF4});                          // JR loop
+    return write_rom_file(name, rom);
+}
+
+// Trace of the next steps plus the state they end in
+static std::string run_and_trace(Emulator& emulator, int steps) {
+    std::ostringstream trace;
+    emulator.set_trace_sink(&trace);
+    for (int i = 0; i < steps; ++i) emulator.step();
+    emulator.set_trace_sink(nullptr);
+    trace << emulator.emulated_cycles() << ' ' << emulator.debug_read_io(0xFF44) << ' ' << emulator.debug_read_io(0xFF05);
+    return trace.str();
+}
+
+static void test_save_state_round_trip() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_timer_loop_rom("save_state_test.gb")));
+
+    for (int i = 0; i < 5000; ++i) emulator.step();
+    std::vector<uint8_t> state = emulator.save_state();
+    std::string first = run_and_trace(emulator, 20000);
+    auto first_frame = emulator.debug_get_ppu().get_framebuffer();
+
+    assert(emulator.load_state(state));
+    std::string second = run_and_trace(emulator, 20000);
+    assert(first == second);
+    assert(first_frame == emulator.debug_get_ppu().get_framebuffer());
+
+    // Truncated data or another format version is rejected and leaves the emulator untouched
+    std::vector<uint8_t> before = emulator.save_state();
+    std::vector<uint8_t> truncated(state.begin(), state.begin() + state.size() / 2);
+    assert(!emulator.load_state(truncated));
+    assert(emulator.save_state() == before);
+    std::vector<uint8_t> wrong_version = state;
+    wrong_version[4] ^= 0xFF;
+    assert(!emulator.load_state(wrong_version));
+    assert(emulator.save_state() == before);
+}
+
+// OAM DMA completes within the write to 0xFF46, so a state saved right after it holds the whole copy
+static void test_save_state_after_dma() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    std::string rom_path = write_timer_loop_rom("save_state_test.gb");
+    assert(emulator.load_rom(rom_path));
+    emulator.debug_write_io(0xFF40, 0x00); // LCD off: OAM readable at any time
+    for (int i = 0; i < 0xA0; ++i) emulator.debug_write_io(0xC000 + i, static_cast<uint8_t>(i ^ 0x5A));
+    emulator.debug_write_io(0xFF46, 0xC0);
+    std::vector<uint8_t> state = emulator.save_state();
+
+    Emulator restored;
+    restored.set_headless(true);
+    assert(restored.load_rom(rom_path));
+    assert(restored.load_state(state));
+    for (int i = 0; i < 0xA0; ++i) assert(restored.debug_read_io(0xFE00 + i) == static_cast<uint8_t>(i ^ 0x5A));
+}
+
+// Each step advances the PPU and the cycle counter by the instruction's real cost (CALL is 24)
+static void test_step_uses_instruction_cycles() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0xCD, 0x60, 0x01});                    // CALL $0160
+    put_code(rom, 0x0160, {0x18, 0xFE});                          // JR -2
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("step_cycles_test.gb", rom)));
+
+    const int expected[] = {4, 16, 24, 12};                       // NOP, JP $0150, CALL, JR
+    for (int cycles : expected) {
+        uint64_t ppu_before = emulator.debug_get_ppu().get_global_cycles();
+        uint64_t total_before = emulator.emulated_cycles();
+        assert(emulator.step() == cycles);
+        assert(emulator.debug_get_ppu().get_global_cycles() - ppu_before == static_cast<uint64_t>(cycles));
+        assert(emulator.emulated_cycles() - total_before == static_cast<uint64_t>(cycles));
+    }
+}
+
+static std::string write_idle_rom(const std::string& name) {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x18, 0xFE});                          // JR -2
+    return write_rom_file(name, rom);
+}
+
+// Counts its calls in WRAM so the emulated machine sees them too
+struct CountingHook : FrameHook {
+    int calls = 0;
+    void on_frame(Emulator& emulator) override {
+        ++calls;
+        emulator.debug_write_io(0xC000, static_cast<uint8_t>(emulator.debug_read_io(0xC000) + 1));
+    }
+};
+
+static void test_frame_hooks_run_headless() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    emulator.debug_write_io(0xC000, 0x00);
+    CountingHook hook;
+    emulator.add_frame_hook(&hook);
+    emulator.add_frame_hook(&hook);                               // registered once
+    for (int i = 0; i < 5; ++i) emulator.run_frame();
+    assert(hook.calls == 5);
+    assert(emulator.debug_read_io(0xC000) == 5);
+
+    emulator.remove_frame_hook(&hook);
+    emulator.run_frame();
+    assert(hook.calls == 5);
+}
+
+// A held button is released for one frame every repeat_rate frames once repeat_delay has passed
+static void test_input_auto_repeat() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    emulator.set_input_config({3, 4});
+    emulator.debug_write_io(0xFF00, 0x10);                        // select the action buttons
+    emulator.set_button(4, true);                                 // A
+    for (int frame = 1; frame <= 12; ++frame) {
+        emulator.debug_write_io(0xFF0F, 0x00);
+        emulator.run_frame();
+        bool released = frame >= 3 && (frame - 3) % 4 == 0;
+        assert(((emulator.debug_read_io(0xFF00) & 0x01) != 0) == released);
+        // A new press edge (and joypad interrupt) on the frame after each release
+        bool repressed = frame >= 4 && (frame - 4) % 4 == 0;
+        assert(((emulator.debug_read_io(0xFF0F) & 0x10) != 0) == repressed);
+    }
+
+    emulator.set_button(4, false);
+    for (int frame = 0; frame < 8; ++frame) {
+        emulator.run_frame();
+        assert(emulator.debug_read_io(0xFF00) & 0x01);
+    }
+}
+
+// Without initialize() there is no window: loading works, run() reports the setup error instead of crashing
+static void test_run_without_window_fails() {
+    Emulator emulator;
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    uint64_t cycles = emulator.emulated_cycles();
+    assert(!emulator.run());
+    assert(emulator.emulated_cycles() == cycles);                 // nothing was executed
+}
+
+// The latency budget sizes the device buffer: half the budget, as a power of two, at least 64 frames
+static void test_audio_latency_sizes_buffer() {
+    Emulator emulator;
+    assert(emulator.get_audio_latency_samples() == 4096);
+    assert(emulator.audio_buffer_frames() == 2048);
+    emulator.set_audio_latency_samples(256);
+    assert(emulator.get_audio_latency_samples() == 256);
+    assert(emulator.audio_buffer_frames() == 128);
+    emulator.set_audio_latency_samples(8192);
+    assert(emulator.audio_buffer_frames() == 4096);
+    emulator.set_audio_latency_samples(3000);
+    assert(emulator.audio_buffer_frames() == 1024);
+    emulator.set_audio_latency_samples(0);                        // clamped to 1
+    assert(emulator.get_audio_latency_samples() == 1);
+    assert(emulator.audio_buffer_frames() == 64);
+}
+
+// CGB speed switch: arm KEY1 bit 0, STOP switches; afterwards DIV counts twice per normal-speed T-cycle
+static void test_cgb_speed_switch() {
+    std::vector<uint8_t> rom = make_rom();
+    rom[0x0143] = 0x80;                                           // CGB compatible
+    put_code(rom, 0x0150, {0x3E, 0x01, 0xE0, 0x4D,                // LD A,$01 ; LDH (KEY1),A
+                           0x10, 0x00,                            // STOP
+                           0x18, 0xFE});                          // JR -2
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("speed_switch_test.gb", rom)));
+    while (emulator.pc() != 0x0154) emulator.step();
+    assert((emulator.debug_read_io(0xFF4D) & 0x81) == 0x01);      // armed, normal speed
+    emulator.step();                                              // STOP
+    assert(emulator.pc() == 0x0156);
+    assert((emulator.debug_read_io(0xFF4D) & 0x81) == 0x80);      // double speed, no longer armed
+    assert(emulator.debug_read_io(0xFF04) == 0);
+
+    uint64_t start = emulator.emulated_cycles();
+    while (emulator.emulated_cycles() - start < 256 * 50) emulator.step();
+    int div = emulator.debug_read_io(0xFF04);
+    assert(div >= 99 && div <= 101);                              // 50 at normal speed
+}
+
+// A bad header checksum only warns by default; strict_header rejects the ROM and keeps the loaded one
+static void test_strict_header_check() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x18, 0xFE});
+    fix_header_checksum(rom);
+    rom[0x014D] ^= 0xFF;
+    std::string bad_path = write_rom_file("bad_checksum_test.gb", rom, false);
+
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_timer_loop_rom("good_header_test.gb"), true));
+    for (int i = 0; i < 100; ++i) emulator.step();
+    std::vector<uint8_t> before = emulator.save_state();
+    assert(!emulator.load_rom(bad_path, true));
+    assert(emulator.save_state() == before);                      // untouched
+
+    Emulator lenient;                                             // default: warning only
+    lenient.set_headless(true);
+    assert(lenient.load_rom(bad_path));
+    assert(lenient.cartridge_header().header_checksum == rom[0x014D]);
+    lenient.step();
+    lenient.step();
+    assert(lenient.pc() == 0x0150);                               // entry point jumped to the program
+}
+
+static std::string write_store_rom(const std::string& name) {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x3E, 0x42,                            // LD A,$42
+                           0xEA, 0x23, 0xC1,                      // $0152: LD ($C123),A
+                           0x18, 0xFE});                          // $0155: JR -2
+    return write_rom_file(name, rom);
+}
+
+static void test_run_frame_stops_at_breakpoint() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_store_rom("store_test.gb")));
+    emulator.debug_write_io(0xC123, 0x00);
+    emulator.add_breakpoint(0x0152);
+    emulator.run_frame();
+    assert(emulator.stop_reason() == CPU::StopReason::Breakpoint);
+    assert(emulator.pc() == 0x0152);
+    assert(emulator.debug_read_io(0xC123) == 0x00);               // the store hasn't run
+    emulator.run_frame();                                         // resumes past the breakpoint
+    assert(emulator.stop_reason() == CPU::StopReason::None);
+    assert(emulator.debug_read_io(0xC123) == 0x42);
+}
+
+static void test_run_frame_stops_at_watchpoint() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_store_rom("store_test.gb")));
+    emulator.add_watchpoint(0xC123, true);
+    emulator.run_frame();
+    assert(emulator.stop_reason() == CPU::StopReason::Watchpoint);
+    assert(emulator.watchpoint_address() == 0xC123);
+    assert(emulator.pc() == 0x0155);                              // stopped after the writing instruction
+    assert(emulator.debug_read_io(0xC123) == 0x42);
+}
+
+// tests/golden_trace.log is this program's real trace with the flags of lines 6-8 edited (F:10 -> F:00),
+// so the comparison must stop at line 6 and report the line before it
+static void test_reference_trace_divergence() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x3E, 0x42,                            // LD A,$42
+                           0x47,                                  // LD B,A
+                           0x04,                                  // loop: INC B
+                           0x18, 0xFD});                          // JR loop
+    std::string rom_path = write_rom_file("golden_trace_test.gb", rom);
+
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(rom_path));
+    assert(emulator.load_reference_trace(std::string(TEST_DATA_DIR) + "/golden_trace.log"));
+    TraceDivergence divergence = emulator.run_against_reference();
+    assert(divergence.diverged);
+    assert(divergence.line == 6);
+    assert(divergence.expected == "A:42 F:00 B:43 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0154 PCMEM:18,FD,00,00");
+    assert(divergence.actual == "A:42 F:10 B:43 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0154 PCMEM:18,FD,00,00");
+    assert(divergence.previous == "A:42 F:B0 B:42 C:13 D:00 E:D8 H:01 L:4D SP:FFFE PC:0153 PCMEM:04,18,FD,00");
+
+    // The unedited first five lines match: all five run, no divergence
+    std::ifstream file(std::string(TEST_DATA_DIR) + "/golden_trace.log");
+    std::vector<std::string> first_lines;
+    for (std::string line; first_lines.size() < 5 && std::getline(file, line);) {
+        first_lines.push_back(line.substr(0, line.find('\r')));
+    }
+    Emulator matching;
+    matching.set_headless(true);
+    assert(matching.load_rom(rom_path));
+    matching.set_reference_trace(first_lines);
+    assert(!matching.run_against_reference().diverged);
+    assert(matching.pc() == 0x0154);
+}
+
+// A JR -2 loop with no interrupts is reported as a hang after about two seconds of frames
+static void test_health_check_detects_hang() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    for (int i = 0; i < 60; ++i) emulator.run_frame();
+    assert(emulator.health_check() == EmulationHealth::Ok);
+    for (int i = 0; i < 80; ++i) emulator.run_frame();
+    assert(emulator.health_check() == EmulationHealth::SuspectedHang);
+
+    // A program that keeps moving is fine
+    Emulator busy;
+    busy.set_headless(true);
+    assert(busy.load_rom(write_timer_loop_rom("save_state_test.gb")));
+    for (int i = 0; i < 140; ++i) busy.run_frame();
+    assert(busy.health_check() == EmulationHealth::Ok);
+}
+
+// One DMG frame is 70224 T-cycles, so 60 frames are about 1.005 s of emulated time
+static void test_emulated_time() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    for (int i = 0; i < 60; ++i) emulator.run_frame();
+    std::chrono::duration<double> elapsed = emulator.emulated_time();
+    assert(std::abs(elapsed.count() - 60.0 * 70224 / 4194304) < 0.002);
+    assert(elapsed.count() == emulator.emulated_cycles() / 4194304.0);
+}
+
+static uint64_t frame_hash(const std::array<uint32_t, 160 * 144>& frame) {
+    uint64_t hash = 1469598103934665603ull;                      // FNV-1a
+    for (uint32_t pixel : frame) {
+        hash ^= pixel;
+        hash *= 1099511628211ull;
+    }
+    return hash;
+}
+
+// Turns the LCD on and keeps filling VRAM with DIV values, so every frame looks different
+static std::string write_vram_noise_rom(const std::string& name) {
+    std::vector<uint8_t> rom = make_rom();
+    // LD A,$E4; LDH (BGP),A; LD A,$91; LDH (LCDC),A; LD HL,$8000; loop: LDH A,(DIV); LD (HL+),A; LD A,H; AND $1F; OR $80; LD H,A; JR loop
+    put_code(rom, 0x0150, {0x3E, 0xE4, 0xE0, 0x47, 0x3E, 0x91, 0xE0, 0x40,
+                           0x21, 0x00, 0x80, 0xF0, 0x04, 0x22, 0x7C, 0xE6, 0x1F, 0xF6, 0x80, 0x67, 0x18, 0xF5});
+    return write_rom_file(name, rom);
+}
+
+// Two runs of the same ROM produce the same frames, bit for bit
+static void test_run_frame_deterministic() {
+    std::string path = write_vram_noise_rom("vram_noise_test.gb");
+
+    std::vector<uint64_t> hashes[2];
+    for (auto& run : hashes) {
+        Emulator emulator;
+        emulator.set_headless(true);
+        assert(emulator.load_rom(path));
+        for (int i = 0; i < 30; ++i) run.push_back(frame_hash(emulator.run_frame()));
+    }
+    assert(hashes[0] == hashes[1]);
+    assert(hashes[0].front() != hashes[0].back());               // the frames actually change
+}
+
+// The frame is pasted at (game_x, game_y); everything around it keeps the border's pixels
+static void test_composited_frame_border() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_vram_noise_rom("vram_noise_test.gb")));
+    BorderImage border;
+    border.width = 200;
+    border.height = 180;
+    border.game_x = 24;
+    border.game_y = 16;
+    border.pixels.assign(200 * 180, 0xFF123456);
+    assert(!emulator.set_border(BorderImage{200, 180, 50, 16, border.pixels}));   // game area doesn't fit
+    assert(emulator.set_border(border));
+    assert(emulator.composited_width() == 200 && emulator.composited_height() == 180);
+
+    for (int i = 0; i < 10; ++i) emulator.run_frame();
+    const auto& frame = emulator.run_frame();
+    std::vector<uint32_t> composited = emulator.composited_frame();
+    assert(composited.size() == 200 * 180);
+    for (int y = 0; y < 180; ++y) {
+        for (int x = 0; x < 200; ++x) {
+            bool game = x >= 24 && x < 24 + 160 && y >= 16 && y < 16 + 144;
+            uint32_t expected = game ? frame[(y - 16) * 160 + (x - 24)] : 0xFF123456;
+            assert(composited[y * 200 + x] == expected);
+        }
+    }
+
+    assert(emulator.set_border(std::nullopt));
+    assert(emulator.composited_frame() == std::vector<uint32_t>(frame.begin(), frame.end()));
+}
+
+// Hit: returns the T-cycles until the store lands. Miss: gives up after max_cycles with -1.
+static void test_run_until_memory() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_store_rom("store_test.gb")));
+    emulator.debug_write_io(0xC123, 0x00);
+    emulator.debug_write_io(0xC124, 0x00);
+    assert(emulator.run_until_memory(0xC123, 0x42, 1000) == 4 + 16 + 8 + 16); // NOP, JP, LD A, LD (a16)
+    assert(emulator.pc() == 0x0155);
+    assert(emulator.run_until_memory(0xC123, 0x42, 1000) == 0);               // already there
+
+    uint64_t start = emulator.emulated_cycles();
+    assert(emulator.run_until_memory(0xC124, 0x01, 1000) == -1);
+    assert(emulator.emulated_cycles() - start >= 1000);
+    assert(emulator.emulated_cycles() - start < 1000 + 24);                     // stops within one instruction
+}
+
+// peek_instruction decodes at PC without executing: PC, cycles, IO state and watchpoints are untouched
+static void test_peek_instruction() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0xCB, 0x7C,                            // BIT 7,H
+                           0x18, 0xFE});                          // JR -2
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("peek_test.gb", rom)));
+    while (emulator.pc() != 0x0150) emulator.step();
+    emulator.debug_write_io(0xFF00, 0x20);
+    emulator.debug_write_io(0xFF01, 0x5A);
+    emulator.set_button(0, true);
+    uint8_t io_before[] = {emulator.debug_read_io(0xFF00), emulator.debug_read_io(0xFF01),
+                           emulator.debug_read_io(0xFF02), emulator.debug_read_io(0xFF0F)};
+    emulator.add_watchpoint(0x0150, false);
+    emulator.add_watchpoint(0x0151, false);
+    uint64_t cycles = emulator.emulated_cycles();
+
+    PeekedInstruction peeked = emulator.peek_instruction();
+    assert(peeked.pc == 0x0150);
+    assert(peeked.opcode == 0xCB);
+    assert(peeked.length == 2);
+    assert(peeked.mnemonic == "BIT 7,H");
+    assert(emulator.pc() == 0x0150);
+    assert(emulator.emulated_cycles() == cycles);
+    assert(emulator.stop_reason() == CPU::StopReason::None);
+    uint8_t io_after[] = {emulator.debug_read_io(0xFF00), emulator.debug_read_io(0xFF01),
+                          emulator.debug_read_io(0xFF02), emulator.debug_read_io(0xFF0F)};
+    assert(std::equal(std::begin(io_before), std::end(io_before), std::begin(io_after)));
+
+    emulator.remove_watchpoint(0x0150, false);
+    emulator.remove_watchpoint(0x0151, false);
+    emulator.step();                                              // nothing was latched by the peek
+    assert(emulator.stop_reason() == CPU::StopReason::None);
+    assert(emulator.pc() == 0x0152);
+}
+
+// override_cycles(0x00, 8): each NOP costs 8 T-cycles, and the timer sees those cycles
+static void test_cycle_override() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("nop_sled_test.gb", make_rom())));   // 0x0150 on: NOPs
+    while (emulator.pc() != 0x0150) emulator.step();
+    assert(emulator.step() == 4);
+    emulator.override_cycles(0x00, 8);
+    assert(emulator.step() == 8);
+
+    emulator.debug_write_io(0xFF04, 0x00);
+    for (int i = 0; i < 64; ++i) emulator.step();                   // 512 T-cycles
+    assert(emulator.debug_read_io(0xFF04) == 2);
+    emulator.clear_cycle_overrides();
+    assert(emulator.step() == 4);
+}
+
+// Register pair accessors round-trip; F's low nibble doesn't exist and always reads 0
+static void test_register_pairs() {
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_idle_rom("idle_test.gb")));
+    emulator.set_bc(0x1234);
+    assert(emulator.bc() == 0x1234);
+    emulator.set_de(0xBEEF);
+    emulator.set_hl(0xC0DE);
+    emulator.set_sp(0xFFFE);
+    assert(emulator.de() == 0xBEEF && emulator.hl() == 0xC0DE && emulator.sp() == 0xFFFE);
+    emulator.set_af(0x12FF);
+    assert(emulator.af() == 0x12F0);
+    emulator.set_af(0x3450);
+    assert(emulator.af() == 0x3450);
+    assert(emulator.bc() == 0x1234);                                // untouched by the AF writes
+}
+
+// End to end through Emulator -> CPU -> MMU: a ROM program runs and its result lands in WRAM
+static void test_emulator_runs_program() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x21, 0x00, 0xC0,                      // LD HL,$C000
+                           0x06, 0x0A,                            // LD B,10
+                           0xAF,                                  // XOR A
+                           0x80,                                  // $0156: ADD A,B
+                           0x05,                                  // DEC B
+                           0x20, 0xFC,                            // JR NZ,$0156
+                           0x77,                                  // LD (HL),A
+                           0x18, 0xFE});                          // $015B: JR -2
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("sum_test.gb", rom)));
+    emulator.debug_write_io(0xC000, 0x00);
+    emulator.run_frame();
+    assert(emulator.debug_read_io(0xC000) == 55);                 // 10 + 9 + ... + 1
+    assert(emulator.pc() == 0x015B);
+    assert(emulator.hl() == 0xC000);
+    assert(emulator.stop_reason() == CPU::StopReason::None);
+}
+
+int main() {
+    test_save_state_round_trip();
+    test_save_state_after_dma();
+    test_step_uses_instruction_cycles();
+    test_frame_hooks_run_headless();
+    test_input_auto_repeat();
+    test_run_without_window_fails();
+    test_audio_latency_sizes_buffer();
+    test_cgb_speed_switch();
+    test_strict_header_check();
+    test_run_frame_stops_at_breakpoint();
+    test_run_frame_stops_at_watchpoint();
+    test_reference_trace_divergence();
+    test_health_check_detects_hang();
+    test_emulated_time();
+    test_run_frame_deterministic();
+    test_composited_frame_border();
+    test_run_until_memory();
+    test_peek_instruction();
+    test_cycle_override();
+    test_register_pairs();
+    test_emulator_runs_program();
+    std::printf("test_emulator: all tests passed\n");
+    return 0;
+}
@@ -0,0 +1,74 @@
+#ifndef TEST_HELPERS_H
+#define TEST_HELPERS_H
+
+// Shared helpers for the unit tests. Each tests/test_*.cpp is its own executable (see CMakeLists.txt);
+// a failed assert aborts it and ctest reports the failure.
+
+// assert() must stay active in Release builds too
+#undef NDEBUG
+#include <cassert>
+#include <cstdint>
+#include <cstdio>
+#include <algorithm>
+#include <filesystem>
+#include <fstream>
+#include <initializer_list>
+#include <string>
+#include <vector>
+#include "cpu.h"
+#include "mmu.h"
+
+// ROM image with a valid header (Nintendo logo; write_rom_file fixes the checksum) so Emulator::load_rom
+// accepts it quietly. The entry point jumps to 0x0150, where tests put their code (see put_code).
+// Size follows the header ROM size code: 32 KB << rom_size_code.
+inline std::vector<uint8_t> make_rom(uint8_t cartridge_type = 0x00, uint8_t rom_size_code = 0x00, uint8_t ram_size_code = 0x00) {
+    static const uint8_t NINTENDO_LOGO[48] = {
+        0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+        0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+        0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E
+    };
+    std::vector<uint8_t> rom(static_cast<size_t>(0x8000) << rom_size_code, 0x00);
+    const uint8_t entry[4] = {0x00, 0xC3, 0x50, 0x01}; // NOP ; JP $0150
+    std::copy(std::begin(entry), std::end(entry), rom.begin() + 0x0100);
+    std::copy(std::begin(NINTENDO_LOGO), std::end(NINTENDO_LOGO), rom.begin() + 0x0104);
+    rom[0x0147] = cartridge_type;
+    rom[0x0148] = rom_size_code;
+    rom[0x0149] = ram_size_code;
+    return rom;
+}
+
+// Recompute the header checksum (0x014D) after patching header bytes
+inline void fix_header_checksum(std::vector<uint8_t>& rom) {
+    uint8_t sum = 0;
+    for (int i = 0x0134; i <= 0x014C; ++i) sum = static_cast<uint8_t>(sum - rom[i] - 1);
+    rom[0x014D] = sum;
+}
+
+// Place code at an address of a ROM image (0x0150 for the program started by the entry point)
+inline void put_code(std::vector<uint8_t>& rom, uint16_t address, std::initializer_list<uint8_t> code) {
+    std::copy(code.begin(), code.end(), rom.begin() + address);
+}
+
+// Scratch files (ROM images, .sav) live in the system temp directory, never in the source or build tree
+inline std::string test_file_path(const std::string& name) {
+    return (std::filesystem::temp_directory_path() / name).string();
+}
+
+// Write a ROM image to the temp directory for Emulator::load_rom (header checksum fixed up first
+// unless fix_checksum is false); returns the full path
+inline std::string write_rom_file(const std::string& name, std::vector<uint8_t> rom, bool fix_checksum = true) {
+    if (fix_checksum) fix_header_checksum(rom);
+    std::string path = test_file_path(name);
+    std::ofstream file(path, std::ios::binary | std::ios::trunc);
+    file.write(reinterpret_cast<const char*>(rom.data()), static_cast<std::streamsize>(rom.size()));
+    return path;
+}
+
+// Copy code into WRAM (0xC000 by default) and point PC at it, for CPU tests on a bare MMU
+inline void load_program(MMU& mmu, CPU& cpu, std::initializer_list<uint8_t> code, uint16_t at = 0xC000) {
+    uint16_t address = at;
+    for (uint8_t byte : code) mmu.write_byte(address++, byte);
+    cpu.PC = at;
+}
+
+#endif // TEST_HELPERS_H
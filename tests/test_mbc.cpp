@@ -0,0 +1,259 @@
+#include "test_helpers.h"
+#include "emulator.h"
+
+static void test_battery_ram_round_trip() {
+    const std::string sav_path = test_file_path("battery_test.sav");
+    std::remove(sav_path.c_str());
+    MMU mmu;
+    mmu.load_rom(make_rom(0x03, 0x00, 0x03)); // MBC1+RAM+BATTERY, 32 KB RAM
+    mmu.write_byte(0x0000, 0x0A);             // enable RAM
+    mmu.write_byte(0xA000, 0x12);
+    mmu.write_byte(0x6000, 0x01);             // RAM banking mode
+    mmu.write_byte(0x4000, 0x03);             // RAM bank 3
+    mmu.write_byte(0xBFFF, 0x34);
+    assert(mmu.save_ram(sav_path));
+
+    MMU restored;
+    restored.load_rom(make_rom(0x03, 0x00, 0x03));
+    assert(restored.load_ram(sav_path));
+    assert(restored.get_external_ram() == mmu.get_external_ram());
+    restored.write_byte(0x0000, 0x0A);
+    assert(restored.read_byte(0xA000) == 0x12);
+    restored.write_byte(0x6000, 0x01);
+    restored.write_byte(0x4000, 0x03);
+    assert(restored.read_byte(0xBFFF) == 0x34);
+
+    // No battery (MBC1+RAM): nothing is written or read
+    MMU volatile_ram;
+    volatile_ram.load_rom(make_rom(0x02, 0x00, 0x03));
+    assert(!volatile_ram.save_ram(sav_path));
+    assert(!volatile_ram.load_ram(sav_path));
+}
+
+// Switching carts must flush the first cart's RAM to its own .sav before it is replaced
+static void test_load_rom_saves_previous_cart() {
+    std::remove(test_file_path("battery_first.sav").c_str());
+    std::remove(test_file_path("battery_second.sav").c_str());
+    Emulator emulator;
+    emulator.set_headless(true);
+    std::string first_rom = write_rom_file("battery_first.gb", make_rom(0x03, 0x00, 0x02));
+    assert(emulator.load_rom(first_rom));
+    emulator.external_ram()[0x0000] = 0x5A;
+    emulator.external_ram()[0x1FFF] = 0xA5;
+    assert(emulator.load_rom(write_rom_file("battery_second.gb", make_rom(0x03, 0x00, 0x02))));
+    assert(emulator.external_ram()[0x0000] == 0x00);
+
+    assert(emulator.load_rom(first_rom));
+    assert(emulator.external_ram()[0x0000] == 0x5A);
+    assert(emulator.external_ram()[0x1FFF] == 0xA5);
+}
+
+static void latch_rtc(MMU& mmu) {
+    mmu.write_byte(0x6000, 0x00);
+    mmu.write_byte(0x6000, 0x01);
+}
+
+static void test_mbc3_rtc() {
+    MMU mmu;
+    mmu.load_rom(make_rom(0x10, 0x00, 0x02)); // MBC3+TIMER+RAM+BATTERY
+    mmu.write_byte(0x0000, 0x0A);
+    mmu.write_byte(0x4000, 0x0C);             // DH: halt the clock so values are deterministic
+    mmu.write_byte(0xA000, 0x40);
+    mmu.write_byte(0x4000, 0x08);             // seconds
+    mmu.write_byte(0xA000, 0xFF);
+    assert(mmu.read_byte(0xA000) == 0x3F);    // 6-bit register
+    mmu.write_byte(0xA000, 30);
+    mmu.write_byte(0x4000, 0x09);             // minutes
+    mmu.write_byte(0xA000, 15);
+    latch_rtc(mmu);
+    mmu.write_byte(0x4000, 0x08);
+    assert(mmu.read_byte(0xA000) == 30);
+    mmu.write_byte(0x4000, 0x09);
+    assert(mmu.read_byte(0xA000) == 15);
+
+    // Running clock: a latched read gives a plausible time of day
+    mmu.write_byte(0x4000, 0x0C);
+    mmu.write_byte(0xA000, 0x00);
+    latch_rtc(mmu);
+    mmu.write_byte(0x4000, 0x08);
+    assert(mmu.read_byte(0xA000) < 60);
+    mmu.write_byte(0x4000, 0x09);
+    assert(mmu.read_byte(0xA000) >= 15 && mmu.read_byte(0xA000) < 60);
+    mmu.write_byte(0x4000, 0x0A);
+    assert(mmu.read_byte(0xA000) < 24);
+
+    // RAM banks are still reachable next to the RTC registers
+    mmu.write_byte(0x4000, 0x00);
+    mmu.write_byte(0xA123, 0x77);
+    assert(mmu.read_byte(0xA123) == 0x77);
+    mmu.write_byte(0x4000, 0x08);
+    assert(mmu.read_byte(0xA123) != 0x77);
+
+    // Plain MBC3 has no clock: 0x08 selects nothing
+    MMU no_rtc;
+    no_rtc.load_rom(make_rom(0x11));
+    no_rtc.write_byte(0x0000, 0x0A);
+    no_rtc.write_byte(0x4000, 0x08);
+    assert(no_rtc.read_byte(0xA000) == 0xFF);
+}
+
+// ROM whose every bank starts with its own bank number
+static std::vector<uint8_t> make_banked_rom(uint8_t cartridge_type, uint8_t rom_size_code) {
+    std::vector<uint8_t> rom = make_rom(cartridge_type, rom_size_code);
+    for (size_t bank = 1; bank < rom.size() / 0x4000; ++bank) rom[bank * 0x4000] = static_cast<uint8_t>(bank);
+    return rom;
+}
+
+static void test_mbc2() {
+    MMU mmu;
+    mmu.load_rom(make_banked_rom(0x05, 0x02)); // MBC2, 128 KB
+    // Address bit 8 set: ROM bank register
+    mmu.write_byte(0x2100, 0x03);
+    assert(mmu.read_byte(0x4000) == 3);
+    mmu.write_byte(0x0100, 0x00);              // bank 0 maps to 1
+    assert(mmu.read_byte(0x4000) == 1);
+    // Address bit 8 clear: RAM enable, even in the upper half
+    mmu.write_byte(0x2000, 0x05);
+    assert(mmu.read_byte(0x4000) == 1);
+    assert(mmu.read_byte(0xA000) == 0xFF);     // RAM still disabled
+    mmu.write_byte(0x2000, 0x0A);
+
+    // 512 x 4-bit RAM: only the low nibble is kept, and it mirrors every 0x200 bytes
+    mmu.write_byte(0xA000, 0xFF);
+    assert(mmu.read_byte(0xA000) == 0x0F);
+    assert(mmu.get_external_ram().size() == 512);
+    mmu.write_byte(0xA1FF, 0x36);
+    assert(mmu.read_byte(0xA1FF) == 0x06);
+    assert(mmu.read_byte(0xA3FF) == 0x06);
+    assert(mmu.read_byte(0xBFFF) == 0x06);
+}
+
+static void test_mbc1_rom_banking() {
+    MMU mmu;
+    mmu.load_rom(make_banked_rom(0x01, 0x02)); // MBC1, 128 KB (8 banks)
+    assert(mmu.read_byte(0x4000) == 1);        // bank 1 after reset
+    mmu.write_byte(0x2000, 0x02);
+    assert(mmu.read_byte(0x4000) == 2);
+    assert(mmu.read_byte(0x0000) == 0x00);     // 0x0000-0x3FFF stays on bank 0
+    mmu.write_byte(0x3FFF, 0x07);
+    assert(mmu.read_byte(0x4000) == 7);
+    mmu.write_byte(0x2000, 0x00);              // bank 0 selects bank 1
+    assert(mmu.read_byte(0x4000) == 1);
+}
+
+// Writes to 0x0000-0x7FFF reach the MBC registers: 0x0A in 0x0000-0x1FFF enables cartridge RAM
+static void test_ram_enable_register() {
+    MMU mmu;
+    mmu.load_rom(make_rom(0x02, 0x00, 0x02)); // MBC1+RAM, 8 KB
+    mmu.write_byte(0xA000, 0x12);             // RAM disabled: dropped
+    mmu.write_byte(0x0000, 0x0A);
+    assert(mmu.read_byte(0xA000) != 0x12);
+    mmu.write_byte(0xA000, 0x34);
+    mmu.write_byte(0xBFFF, 0x56);
+    assert(mmu.read_byte(0xA000) == 0x34);
+    assert(mmu.read_byte(0xBFFF) == 0x56);
+    mmu.write_byte(0x0000, 0x00);             // disable again: reads float, contents kept
+    assert(mmu.read_byte(0xA000) == 0xFF);
+    mmu.write_byte(0x1FFF, 0x0A);
+    assert(mmu.read_byte(0xA000) == 0x34);
+}
+
+// Accesses past the allocated RAM are dropped instead of wrapping into it
+static void test_ram_write_past_end_ignored() {
+    MMU mmu;
+    mmu.load_rom(make_rom(0x02, 0x00, 0x01)); // MBC1+RAM, 2 KB: 0xA000-0xA7FF
+    mmu.write_byte(0x0000, 0x0A);
+    mmu.write_byte(0xA000, 0x11);
+    mmu.write_byte(0xA7FF, 0x22);
+    mmu.write_byte(0xA800, 0x33);             // one byte past the end
+    assert(mmu.read_byte(0xA800) == 0xFF);
+    assert(mmu.read_byte(0xA000) == 0x11);
+    assert(mmu.read_byte(0xA7FF) == 0x22);
+}
+
+// MBC::get_ram_bank reads 0xFF while the RAM enable latch is off, for every RAM-carrying MBC
+static void test_get_ram_bank_disabled() {
+    for (MBCType type : {MBC1_RAM, MBC3_RAM, MBC5_RAM}) {
+        std::vector<uint8_t> rom = make_rom(type, 0x00, 0x02);
+        std::vector<uint8_t> ram(8 * 1024, 0x5A);
+        MBC mbc(type, rom, ram);
+        assert(!mbc.is_ram_enabled());
+        assert(mbc.get_ram_bank(0xA000) == 0xFF);
+        mbc.handle_write(0x0000, 0x0A);
+        assert(mbc.get_ram_bank(0xA000) == 0x5A);
+        mbc.handle_write(0x0000, 0x00);
+        assert(mbc.get_ram_bank(0xA000) == 0xFF);
+        assert(mbc.get_ram_bank(0xBFFF) == 0xFF);
+    }
+}
+
+// external_ram() is the flat RAM behind 0xA000-0xBFFF: bank n starts at n * 0x2000
+static void test_external_ram_write_through() {
+    std::remove(test_file_path("ext_ram_test.sav").c_str());
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("ext_ram_test.gb", make_rom(0x03, 0x00, 0x03)))); // MBC1+RAM+BATTERY, 32 KB
+    assert(emulator.external_ram().size() == 32 * 1024);
+    emulator.external_ram()[0x0000] = 0x12;
+    emulator.external_ram()[0x2005] = 0x34;
+    emulator.debug_write_io(0x0000, 0x0A);                           // enable RAM
+    assert(emulator.debug_read_io(0xA000) == 0x12);
+    emulator.debug_write_io(0x6000, 0x01);                           // RAM banking mode
+    emulator.debug_write_io(0x4000, 0x01);                           // RAM bank 1
+    assert(emulator.debug_read_io(0xA005) == 0x34);
+    emulator.debug_write_io(0xA006, 0x56);
+    assert(emulator.external_ram()[0x2006] == 0x56);
+}
+
+// Snapshot only the banking registers: bank 5 comes back after switching away
+static void test_cartridge_state_round_trip() {
+    MMU mmu;
+    mmu.load_rom(make_banked_rom(0x01, 0x02)); // MBC1, 128 KB
+    mmu.write_byte(0x2000, 0x05);
+    MBC::State state = mmu.get_cartridge_state();
+    assert(state.rom_bank == 5);
+    assert(!state.ram_enabled);
+    mmu.write_byte(0x2000, 0x01);
+    assert(mmu.read_byte(0x4000) == 1);
+    mmu.set_cartridge_state(state);
+    assert(mmu.read_byte(0x4000) == 5);
+    assert(mmu.get_cartridge_state().rom_bank == 5);
+}
+
+// A 0x200-byte ROM (header plus a little code) loads through the unchecked MMU path: reads past
+// its end, in either ROM area and in the (absent) cartridge RAM, return 0xFF
+static void test_tiny_rom_reads_past_end() {
+    MMU mmu;
+    mmu.load_rom(make_rom(0x03, 0x00, 0x02));                      // previous cartridge with RAM
+    std::vector<uint8_t> tiny = make_rom();
+    tiny.resize(0x200);
+    for (size_t i = 0x150; i < tiny.size(); ++i) tiny[i] = static_cast<uint8_t>(i ^ 0xA5);
+    assert(mmu.load_rom(tiny));
+    assert(mmu.get_cartridge_header().cartridge_type == 0x00);
+    assert(mmu.get_external_ram().empty());
+    assert(mmu.read_byte(0x0150) == static_cast<uint8_t>(0x150 ^ 0xA5));
+    assert(mmu.read_byte(0x01FF) == static_cast<uint8_t>(0x1FF ^ 0xA5));
+    assert(mmu.read_byte(0x0200) == 0xFF);
+    assert(mmu.read_byte(0x3FFF) == 0xFF);
+    mmu.write_byte(0x2000, 0x03);                                    // no MBC: ignored
+    assert(mmu.read_byte(0x4000) == 0xFF);
+    assert(mmu.read_byte(0x7FFF) == 0xFF);
+    mmu.write_byte(0x0000, 0x0A);
+    assert(mmu.read_byte(0xA000) == 0xFF);
+}
+
+int main() {
+    test_battery_ram_round_trip();
+    test_load_rom_saves_previous_cart();
+    test_mbc3_rtc();
+    test_mbc2();
+    test_mbc1_rom_banking();
+    test_ram_enable_register();
+    test_ram_write_past_end_ignored();
+    test_get_ram_bank_disabled();
+    test_external_ram_write_through();
+    test_cartridge_state_round_trip();
+    test_tiny_rom_reads_past_end();
+    std::printf("test_mbc: all tests passed\n");
+    return 0;
+}
@@ -0,0 +1,229 @@
+#include "test_helpers.h"
+
+static void test_open_bus_shim() {
+    MMU mmu;
+    mmu.load_rom(make_rom(0x01)); // MBC1, no RAM
+    CompatShims shims;
+    shims.open_bus = true;
+    shims.open_bus_value = 0x42;
+    mmu.apply_compat_shims(shims);
+    assert(mmu.read_byte(0xFEA0) == 0x42);
+    assert(mmu.read_byte(0xFF03) == 0x42);
+    assert(mmu.read_byte(0xFF4C) == 0x42);
+    mmu.write_byte(0x0000, 0x0A);
+    assert(mmu.read_byte(0xA000) == 0x42); // no cartridge RAM behind the enable
+    mmu.write_byte(0xC000, 0x99);
+    assert(mmu.read_byte(0xC000) == 0x99); // mapped memory is unaffected
+
+    // MBC3+TIMER+BATTERY has no RAM, but the selected RTC register is mapped
+    MMU rtc;
+    rtc.load_rom(make_rom(0x0F));
+    rtc.apply_compat_shims(shims);
+    rtc.write_byte(0x4000, 0x08);           // select RTC seconds
+    assert(rtc.read_byte(0xA000) == 0x42);  // RAM/RTC not enabled yet
+    rtc.write_byte(0x0000, 0x0A);
+    rtc.write_byte(0xA000, 0x2A);
+    assert(rtc.read_byte(0xA000) == 0x2A);
+    rtc.write_byte(0x4000, 0x00);           // RAM bank 0: nothing there
+    assert(rtc.read_byte(0xA000) == 0x42);
+}
+
+static void test_ram_fill_shim() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    CompatShims shims;
+    shims.ram_fill = true;
+    shims.ram_fill_value = 0xA5;
+    mmu.apply_compat_shims(shims);
+    assert(mmu.read_byte(0xC000) == 0xA5);
+    assert(mmu.read_byte(0xDFFF) == 0xA5);
+    assert(mmu.read_byte(0xFF80) == 0xA5);
+    assert(mmu.read_byte(0xFFFE) == 0xA5);
+}
+
+static void test_cgb_vram_and_wram_banks() {
+    std::vector<uint8_t> rom = make_rom();
+    rom[0x0143] = 0xC0;            // CGB only
+    MMU mmu;
+    mmu.load_rom(rom);
+    mmu.write_byte(0xFF40, 0x00);  // LCD off: VRAM always accessible
+
+    // VRAM: bank 0 and bank 1 hold independent data
+    mmu.write_byte(0xFF4F, 0x00);
+    mmu.write_byte(0x8000, 0xAA);
+    mmu.write_byte(0xFF4F, 0x01);
+    assert(mmu.read_byte(0xFF4F) == 0xFF);
+    mmu.write_byte(0x8000, 0xBB);
+    assert(mmu.read_byte(0x8000) == 0xBB);
+    mmu.write_byte(0xFF4F, 0x00);
+    assert(mmu.read_byte(0xFF4F) == 0xFE);
+    assert(mmu.read_byte(0x8000) == 0xAA);
+
+    // WRAM: 0xC000-0xCFFF is always bank 0, 0xD000-0xDFFF is bank 1-7 (0 selects 1)
+    mmu.write_byte(0xC000, 0xC0);
+    mmu.write_byte(0xFF70, 0x00);
+    mmu.write_byte(0xD000, 0x10);
+    mmu.write_byte(0xFF70, 0x01);
+    assert(mmu.read_byte(0xD000) == 0x10);
+    for (uint8_t bank = 2; bank < 8; ++bank) {
+        mmu.write_byte(0xFF70, bank);
+        mmu.write_byte(0xD000, bank);
+    }
+    for (uint8_t bank = 2; bank < 8; ++bank) {
+        mmu.write_byte(0xFF70, bank);
+        assert(mmu.read_byte(0xFF70) == (0xF8 | bank));
+        assert(mmu.read_byte(0xD000) == bank);
+        assert(mmu.read_byte(0xF000) == bank); // echo RAM follows the selected bank
+        assert(mmu.read_byte(0xC000) == 0xC0);
+    }
+    mmu.write_byte(0xFF70, 0x01);
+    assert(mmu.read_byte(0xD000) == 0x10);
+
+    // DMG: the bank registers do nothing
+    MMU dmg;
+    dmg.load_rom(make_rom());
+    dmg.write_byte(0xFF70, 0x03);
+    dmg.write_byte(0xD000, 0x01);
+    dmg.write_byte(0xFF70, 0x05);
+    assert(dmg.read_byte(0xD000) == 0x01);
+}
+
+// Step the PPU until STAT reports the mode
+static void step_to_mode(MMU& mmu, uint8_t mode) {
+    for (int i = 0; i < 70224 && (mmu.read_byte(0xFF41) & 0x03) != mode; ++i) mmu.get_ppu().step(1, mmu);
+    assert((mmu.read_byte(0xFF41) & 0x03) == mode);
+}
+
+static void test_vram_oam_locked_by_ppu_mode() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF40, 0x00);
+    mmu.write_byte(0x8000, 0x5A);
+    mmu.write_byte(0xFE00, 0x33);
+    mmu.write_byte(0xFF40, 0x91);
+
+    step_to_mode(mmu, 2);                  // OAM scan: OAM locked, VRAM open
+    assert(mmu.read_byte(0xFE00) == 0xFF);
+    assert(mmu.read_byte(0x8000) == 0x5A);
+    step_to_mode(mmu, 3);                  // pixel transfer: both locked, writes dropped
+    assert(mmu.read_byte(0x8000) == 0xFF);
+    assert(mmu.read_byte(0xFE00) == 0xFF);
+    mmu.write_byte(0x8000, 0x11);
+    step_to_mode(mmu, 0);                  // H-Blank: both open again
+    assert(mmu.read_byte(0x8000) == 0x5A);
+    assert(mmu.read_byte(0xFE00) == 0x33);
+}
+
+// A second write to 0xFF46 restarts OAM DMA from the new source (DMA is instantaneous here, so it simply recopies)
+static void test_oam_dma_retrigger() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF40, 0x00);              // LCD off: OAM readable at any time
+    for (int i = 0; i < 0xA0; ++i) {
+        mmu.write_byte(0xC000 + i, static_cast<uint8_t>(i));
+        mmu.write_byte(0xC100 + i, static_cast<uint8_t>(0xFF - i));
+    }
+    mmu.write_byte(0xFF46, 0xC0);
+    mmu.write_byte(0xFF46, 0xC1);
+    for (int i = 0; i < 0xA0; ++i) assert(mmu.read_byte(0xFE00 + i) == static_cast<uint8_t>(0xFF - i));
+    assert(mmu.read_byte(0xFF46) == 0xC1);
+}
+
+// AccuracyLevel::Fast drops the VRAM lock, the timer write glitch, STAT blocking and the HALT bug
+static void test_accuracy_level() {
+    for (AccuracyLevel level : {AccuracyLevel::Accurate, AccuracyLevel::Fast}) {
+        bool accurate = level == AccuracyLevel::Accurate;
+        MMU mmu;
+        mmu.load_rom(make_rom());
+        mmu.set_accuracy(level);
+        mmu.write_byte(0xFF40, 0x00);
+        mmu.write_byte(0x8000, 0x5A);
+        mmu.write_byte(0xFF40, 0x91);
+        step_to_mode(mmu, 3);
+        assert(mmu.read_byte(0x8000) == (accurate ? 0xFF : 0x5A));
+
+        // Mode 0 -> mode 2 keeps the STAT line high: only Fast raises IF bit 1 for mode 2
+        mmu.write_byte(0xFF41, 0x28);
+        step_to_mode(mmu, 0);
+        mmu.write_byte(0xFF0F, 0x00);
+        step_to_mode(mmu, 2);
+        assert(((mmu.read_byte(0xFF0F) & 0x02) != 0) == !accurate);
+
+        // Resetting DIV while the TAC-selected bit 3 is set clocks TIMA only at Accurate
+        mmu.write_byte(0xFF04, 0x00);
+        mmu.write_byte(0xFF05, 0x00);
+        mmu.write_byte(0xFF07, 0x05);
+        mmu.update_timer_cycles(8);
+        assert(mmu.read_byte(0xFF05) == 0x00);
+        mmu.write_byte(0xFF04, 0x00);
+        assert(mmu.read_byte(0xFF05) == (accurate ? 0x01 : 0x00));
+
+        // HALT with IME=0 and a pending interrupt: the HALT bug repeats the next byte only at Accurate
+        CPU cpu(mmu);
+        load_program(mmu, cpu, {0x76, 0x3C, 0x00}); // HALT; INC A; NOP
+        cpu.ime = false;
+        cpu.A = 0;
+        mmu.write_byte(0xFFFF, 0x04);
+        mmu.write_byte(0xFF0F, 0x04);
+        for (int i = 0; i < 3; ++i) cpu.step();
+        assert(cpu.A == (accurate ? 2 : 1));
+    }
+}
+
+// DMA from WRAM page 0xC1 and from its echo page 0xE1 copy the same bytes
+static void test_oam_dma_from_wram_and_echo() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF40, 0x00);
+    for (int i = 0; i < 0xA0; ++i) mmu.write_byte(0xC100 + i, static_cast<uint8_t>(i * 7 + 3));
+    mmu.write_byte(0xFF46, 0xC1);
+    for (int i = 0; i < 0xA0; ++i) assert(mmu.read_byte(0xFE00 + i) == static_cast<uint8_t>(i * 7 + 3));
+
+    for (int i = 0; i < 0xA0; ++i) mmu.write_byte(0xFE00 + i, 0x00);
+    mmu.write_byte(0xFF46, 0xE1);
+    for (int i = 0; i < 0xA0; ++i) assert(mmu.read_byte(0xFE00 + i) == static_cast<uint8_t>(i * 7 + 3));
+}
+
+// peek_byte returns the same value as read_byte but never latches a read watchpoint
+static void test_peek_byte_skips_watchpoints() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xC010, 0x77);
+    mmu.add_watchpoint(0xC010, false);
+    uint16_t address = 0;
+    assert(mmu.peek_byte(0xC010) == 0x77);
+    assert(!mmu.take_watchpoint_hit(address));
+    assert(mmu.read_byte(0xC010) == 0x77);
+    assert(mmu.take_watchpoint_hit(address) && address == 0xC010);
+}
+
+// With both P1 select lines low, the low nibble is the AND of the direction and button groups
+static void test_joypad_both_groups_selected() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.set_joypad_bit(0, true);                // Right (bit 0 of the directions)
+    mmu.set_joypad_bit(4, true);                // A     (bit 0 of the buttons): overlaps Right
+    mmu.set_joypad_bit(5, true);                // B     (bit 1 of the buttons)
+    mmu.write_byte(0xFF00, 0x20);               // directions only
+    assert(mmu.read_byte(0xFF00) == 0xEE);
+    mmu.write_byte(0xFF00, 0x10);               // buttons only
+    assert(mmu.read_byte(0xFF00) == 0xDC);
+    mmu.write_byte(0xFF00, 0x00);               // both: 1110 & 1100
+    assert(mmu.read_byte(0xFF00) == 0xCC);
+    mmu.write_byte(0xFF00, 0x30);               // neither
+    assert(mmu.read_byte(0xFF00) == 0xFF);
+}
+
+int main() {
+    test_open_bus_shim();
+    test_ram_fill_shim();
+    test_cgb_vram_and_wram_banks();
+    test_vram_oam_locked_by_ppu_mode();
+    test_oam_dma_retrigger();
+    test_accuracy_level();
+    test_oam_dma_from_wram_and_echo();
+    test_peek_byte_skips_watchpoints();
+    test_joypad_both_groups_selected();
+    std::printf("test_mmu: all tests passed\n");
+    return 0;
+}
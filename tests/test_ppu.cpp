@@ -0,0 +1,542 @@
+#include "test_helpers.h"
+
+static void lcd_on(MMU& mmu, uint8_t lcdc = 0x91) {
+    mmu.write_byte(0xFF40, 0x00);
+    mmu.write_byte(0xFF40, lcdc);
+}
+
+// Step the PPU one T-cycle at a time until the condition holds (at most two frames)
+template <typename Condition>
+static void step_until(MMU& mmu, Condition condition) {
+    for (int i = 0; i < 2 * 70224; ++i) {
+        if (condition()) return;
+        mmu.get_ppu().step(1, mmu);
+    }
+    assert(!"PPU never reached the expected state");
+}
+
+static uint8_t stat_mode(MMU& mmu) { return mmu.read_byte(0xFF41) & 0x03; }
+
+// DMG setup for rendering tests: LCD off (VRAM/OAM writable), identity palettes, empty OAM
+static void setup_dmg(MMU& mmu) {
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF40, 0x00);
+    mmu.write_byte(0xFF47, 0xE4);
+    mmu.write_byte(0xFF48, 0xE4);
+    mmu.write_byte(0xFF49, 0xE4);
+    for (uint16_t address = 0xFE00; address < 0xFEA0; ++address) mmu.write_byte(address, 0x00);
+}
+
+// Every pixel of the tile gets the same color index
+static void fill_tile(MMU& mmu, uint8_t tile, uint8_t color) {
+    for (int row = 0; row < 8; ++row) {
+        mmu.write_byte(0x8000 + tile * 16 + row * 2, (color & 1) ? 0xFF : 0x00);
+        mmu.write_byte(0x8000 + tile * 16 + row * 2 + 1, (color & 2) ? 0xFF : 0x00);
+    }
+}
+
+static void set_sprite(MMU& mmu, int index, uint8_t y, uint8_t x, uint8_t tile, uint8_t flags = 0x00) {
+    uint16_t address = 0xFE00 + index * 4;
+    mmu.write_byte(address, y);
+    mmu.write_byte(address + 1, x);
+    mmu.write_byte(address + 2, tile);
+    mmu.write_byte(address + 3, flags);
+}
+
+// Turn the LCD on with lcdc and run until scanline ly has been drawn
+static void render_through_line(MMU& mmu, uint8_t lcdc, uint8_t ly) {
+    lcd_on(mmu, lcdc);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == ly + 1; });
+}
+
+static uint32_t pixel(MMU& mmu, int x, int y) { return mmu.get_ppu().get_framebuffer()[y * 160 + x]; }
+
+static void test_stat_lyc_interrupt() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    lcd_on(mmu);
+    mmu.write_byte(0xFF41, 0x40); // LYC=LY source
+    mmu.write_byte(0xFF45, 5);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 4; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 5; });
+    assert(mmu.read_byte(0xFF41) & 0x04);
+    assert(mmu.read_byte(0xFF0F) & 0x02);
+}
+
+// The four sources share one line; a source turning on while it is already high raises nothing
+static void test_stat_blocking() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    lcd_on(mmu);
+    mmu.write_byte(0xFF41, 0x48); // LYC=LY + mode 0
+    mmu.write_byte(0xFF45, 5);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 5 && stat_mode(mmu) == 3; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return stat_mode(mmu) == 0; });  // H-Blank of line 5: line already high
+    assert((mmu.read_byte(0xFF0F) & 0x02) == 0);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 6 && stat_mode(mmu) == 3; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return stat_mode(mmu) == 0; });  // H-Blank of line 6: rising edge
+    assert(mmu.read_byte(0xFF0F) & 0x02);
+
+    // Each enabled mode fires once per line
+    mmu.write_byte(0xFF41, 0x20); // mode 2
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 10 && stat_mode(mmu) == 3; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 11 && stat_mode(mmu) == 2; });
+    assert(mmu.read_byte(0xFF0F) & 0x02);
+}
+
+static void test_cgb_palette_ram() {
+    MMU dmg;
+    dmg.load_rom(make_rom());
+    assert(!dmg.is_cgb_mode());
+
+    std::vector<uint8_t> rom = make_rom();
+    rom[0x0143] = 0x80;                   // CGB enhanced
+    MMU mmu;
+    mmu.load_rom(rom);
+    assert(mmu.is_cgb_mode());
+
+    // BCPS auto-increment: two writes fill palette 1 color 0 (red = 0x001F)
+    mmu.write_byte(0xFF68, 0x80 | 0x08);
+    mmu.write_byte(0xFF69, 0x1F);
+    mmu.write_byte(0xFF69, 0x00);
+    assert(mmu.read_byte(0xFF68) == (0x80 | 0x40 | 0x0A));
+    mmu.write_byte(0xFF68, 0x08);
+    assert(mmu.read_byte(0xFF69) == 0x1F);
+    mmu.write_byte(0xFF68, 0x09);
+    assert(mmu.read_byte(0xFF69) == 0x00);
+    // Index wraps at 64 bytes
+    mmu.write_byte(0xFF68, 0x80 | 0x3F);
+    mmu.write_byte(0xFF69, 0x12);
+    assert((mmu.read_byte(0xFF68) & 0x3F) == 0x00);
+    // OBJ palettes are separate
+    mmu.write_byte(0xFF6A, 0x80);
+    mmu.write_byte(0xFF6B, 0xE0);
+    mmu.write_byte(0xFF6A, 0x00);
+    assert(mmu.read_byte(0xFF6B) == 0xE0);
+    mmu.write_byte(0xFF68, 0x00);
+    assert(mmu.read_byte(0xFF69) != 0xE0);
+
+    // Render: map entry 0 uses tile 0 (color 0) with the bank-1 attribute selecting palette 1
+    mmu.write_byte(0xFF40, 0x00);
+    mmu.write_byte(0xFF4F, 0x01);
+    mmu.write_byte(0x9800, 0x01);
+    mmu.write_byte(0xFF4F, 0x00);
+    lcd_on(mmu);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 1; });
+    const auto& framebuffer = mmu.get_ppu().get_framebuffer();
+    assert(framebuffer[0] == 0xFFFF0000);  // palette 1 color 0
+    assert(framebuffer[8] == 0xFFFFFFFF);  // next map entry: palette 0, still white
+}
+
+static void test_obj_disabled_hides_sprites() {
+    MMU mmu;
+    setup_dmg(mmu);
+    fill_tile(mmu, 1, 3);
+    set_sprite(mmu, 0, 16, 8, 1);     // top-left corner
+    render_through_line(mmu, 0x91, 0); // OBJ off (LCDC bit 1 clear)
+    uint32_t background = pixel(mmu, 80, 0);
+    for (int x = 0; x < 8; ++x) assert(pixel(mmu, x, 0) == background);
+
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 0; }); // next frame
+    render_through_line(mmu, 0x93, 0); // OBJ on
+    for (int x = 0; x < 8; ++x) assert(pixel(mmu, x, 0) != background);
+}
+
+static void test_vblank_raises_stat_mode1() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    lcd_on(mmu);
+    mmu.write_byte(0xFF41, 0x10); // mode 1 source
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 143; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 144; });
+    mmu.get_ppu().step(4, mmu);
+    assert(stat_mode(mmu) == 1);
+    assert((mmu.read_byte(0xFF0F) & 0x03) == 0x03); // VBlank and STAT together
+
+    // Without the STAT source only VBlank is requested
+    mmu.write_byte(0xFF41, 0x00);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 143; });
+    mmu.write_byte(0xFF0F, 0x00);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 144; });
+    mmu.get_ppu().step(4, mmu);
+    assert((mmu.read_byte(0xFF0F) & 0x03) == 0x01);
+}
+
+static void test_sprite_edge_clipping() {
+    MMU mmu;
+    setup_dmg(mmu);
+    for (int row = 0; row < 8; ++row) {     // tile 1: left half transparent, right half color 3
+        mmu.write_byte(0x8010 + row * 2, 0x0F);
+        mmu.write_byte(0x8011 + row * 2, 0x0F);
+    }
+    set_sprite(mmu, 0, 16, 4, 1);           // X=4: columns 0-3 are off the left edge
+    set_sprite(mmu, 1, 0, 40, 1);           // Y=0: fully above the screen
+    set_sprite(mmu, 2, 16, 0, 1);           // X=0: fully left of the screen
+    set_sprite(mmu, 3, 16, 168, 1);         // X=168: fully right of the screen
+    render_through_line(mmu, 0x93, 0);
+    uint32_t background = pixel(mmu, 80, 0);
+    for (int x = 0; x < 4; ++x) assert(pixel(mmu, x, 0) != background); // sprite columns 4-7
+    for (int x = 4; x < 160; ++x) assert(pixel(mmu, x, 0) == background);
+
+    // The same tile at X=8 shows its transparent left half first
+    setup_dmg(mmu);
+    set_sprite(mmu, 0, 16, 8, 1);
+    render_through_line(mmu, 0x93, 0);
+    for (int x = 0; x < 4; ++x) assert(pixel(mmu, x, 0) == background);
+    for (int x = 4; x < 8; ++x) assert(pixel(mmu, x, 0) != background);
+}
+
+// Hardware keeps the first 10 sprites in OAM order, whatever their X
+static void test_ten_sprites_per_line() {
+    MMU mmu;
+    setup_dmg(mmu);
+    fill_tile(mmu, 1, 3);
+    for (int i = 0; i < 10; ++i) set_sprite(mmu, i, 16, static_cast<uint8_t>(160 - i * 12), 1);
+    set_sprite(mmu, 10, 16, 8, 1);   // lowest X, but 11th in OAM: dropped
+    set_sprite(mmu, 11, 16, 24, 1);  // dropped
+    set_sprite(mmu, 12, 40, 8, 1);   // other line: doesn't count
+
+    std::vector<SpriteRenderer::Sprite> selected = mmu.get_ppu().sprites_on_line(mmu, 0);
+    assert(selected.size() == 10);
+    for (const auto& sprite : selected) assert(sprite.oam_index < 10);
+
+    render_through_line(mmu, 0x93, 0);
+    uint32_t background = pixel(mmu, 32, 0);                              // left of every drawn sprite
+    for (int x = 0; x < 24; ++x) assert(pixel(mmu, x, 0) == background); // sprites 10 and 11
+    assert(pixel(mmu, 152, 0) != background);                            // sprite 0
+    assert(pixel(mmu, 44, 0) != background);                             // sprite 9 (X=52)
+}
+
+static void test_window_line_counter() {
+    MMU mmu;
+    setup_dmg(mmu);
+    // Tile 2: row 0 color 3, row 1 color 1, the rest color 0
+    mmu.write_byte(0x8020, 0xFF);
+    mmu.write_byte(0x8021, 0xFF);
+    mmu.write_byte(0x8022, 0xFF);
+    for (uint16_t address = 0x9C00; address < 0xA000; ++address) mmu.write_byte(address, 0x02); // window map
+    mmu.write_byte(0xFF4A, 72);  // WY
+    mmu.write_byte(0xFF4B, 7);   // WX: left edge
+    const uint8_t lcdc = 0xF1;   // LCD, window map 0x9C00, window on, tiles 0x8000, BG on
+
+    render_through_line(mmu, lcdc, 72);
+    uint32_t background = pixel(mmu, 0, 71);
+    uint32_t window_row0 = pixel(mmu, 0, 72);
+    assert(window_row0 != background);
+    for (int x = 0; x < 160; ++x) assert(pixel(mmu, x, 72) == window_row0);
+
+    // Hide the window on lines 73-74: its line counter must not advance
+    mmu.write_byte(0xFF40, lcdc & ~0x20);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 75; });
+    mmu.write_byte(0xFF40, lcdc);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 76; });
+    assert(pixel(mmu, 0, 73) == background);
+    assert(pixel(mmu, 0, 74) == background);
+    uint32_t window_row1 = pixel(mmu, 0, 75);
+    assert(window_row1 != background && window_row1 != window_row0);
+
+    // The counter restarts every frame
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 73; });
+    assert(pixel(mmu, 0, 72) == window_row0);
+}
+
+// Whole frame from a checkerboard BG map plus one sprite in front of the BG and one behind it
+static void test_composited_frame_golden() {
+    const std::array<uint32_t, 4> shades = {0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000};
+    MMU mmu;
+    setup_dmg(mmu);
+    mmu.get_ppu().set_palette(shades);
+    fill_tile(mmu, 0, 0);
+    fill_tile(mmu, 1, 1);
+    fill_tile(mmu, 3, 3);
+    for (int ty = 0; ty < 32; ++ty) {
+        for (int tx = 0; tx < 32; ++tx) mmu.write_byte(0x9800 + ty * 32 + tx, (tx + ty) % 2);
+    }
+    set_sprite(mmu, 0, 30 + 16, 20 + 8, 3);        // in front of the BG
+    set_sprite(mmu, 1, 50 + 16, 60 + 8, 3, 0x80);  // behind BG colors 1-3
+    render_through_line(mmu, 0x93, 143);
+
+    for (int y = 0; y < 144; ++y) {
+        for (int x = 0; x < 160; ++x) {
+            int color = ((x / 8 + y / 8) % 2) ? 1 : 0;
+            bool front = x >= 20 && x < 28 && y >= 30 && y < 38;
+            bool behind = x >= 60 && x < 68 && y >= 50 && y < 58;
+            if (front || (behind && color == 0)) color = 3;
+            assert(pixel(mmu, x, y) == shades[color]);
+        }
+    }
+}
+
+// Steps until the value read changes; returns how many T-cycles that took
+template <typename Read>
+static int cycles_until_change(MMU& mmu, Read read) {
+    auto start = read();
+    for (int cycles = 1; cycles <= 2 * 70224; ++cycles) {
+        mmu.get_ppu().step(1, mmu);
+        if (read() != start) return cycles;
+    }
+    assert(!"value never changed");
+    return -1;
+}
+
+static void test_mode_timing_and_frame_length() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    lcd_on(mmu);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 10 && stat_mode(mmu) == 0; });
+    auto mode = [&] { return stat_mode(mmu); };
+    cycles_until_change(mmu, mode);                    // sync: H-Blank -> OAM scan of line 11
+    assert(cycles_until_change(mmu, mode) == 80);      // mode 2
+    assert(cycles_until_change(mmu, mode) == 172);     // mode 3
+    assert(cycles_until_change(mmu, mode) == 204);     // mode 0
+
+    auto ly = [&] { return mmu.read_byte(0xFF44); };
+    cycles_until_change(mmu, ly);                      // sync to a line start
+    for (int line = 0; line < 10; ++line) assert(cycles_until_change(mmu, ly) == 456);
+
+    // Frame: start of line 144 (VBlank) to the next one
+    auto in_vblank_line = [&] { return mmu.read_byte(0xFF44) == 144; };
+    step_until(mmu, in_vblank_line);
+    assert(cycles_until_change(mmu, in_vblank_line) == 456);
+    assert(cycles_until_change(mmu, in_vblank_line) == 70224 - 456);
+}
+
+// 8x16 sprites ignore bit 0 of the tile index (top = index & 0xFE, bottom = index | 1); 8x8 sprites use it as is
+static void test_tall_sprite_tile_index() {
+    MMU mmu;
+    setup_dmg(mmu);
+    fill_tile(mmu, 2, 1);
+    fill_tile(mmu, 3, 2);
+    set_sprite(mmu, 0, 16, 8, 3);                      // odd index
+    const auto& shades = mmu.get_ppu().get_palette();
+    render_through_line(mmu, 0x97, 8);                 // OBJ on, 8x16
+    assert(pixel(mmu, 0, 0) == shades[1]);             // top half: tile 2
+    assert(pixel(mmu, 0, 8) == shades[2]);             // bottom half: tile 3
+
+    setup_dmg(mmu);
+    set_sprite(mmu, 0, 16, 8, 3);
+    render_through_line(mmu, 0x93, 0);                 // OBJ on, 8x8
+    assert(pixel(mmu, 0, 0) == shades[2]);             // tile 3
+}
+
+// A BGP write mid-frame only affects the lines drawn after it
+static void test_mid_frame_bgp_change() {
+    MMU mmu;
+    setup_dmg(mmu);
+    fill_tile(mmu, 0, 1);                              // whole background: color 1
+    render_through_line(mmu, 0x91, 71);
+    mmu.write_byte(0xFF47, 0xFF);                      // line 72 starts: every color -> shade 3
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 144; });
+    const auto& shades = mmu.get_ppu().get_palette();
+    for (int y = 0; y < 144; ++y) {
+        assert(pixel(mmu, 0, y) == (y < 72 ? shades[1] : shades[3]));
+        assert(pixel(mmu, 159, y) == pixel(mmu, 0, y));
+    }
+}
+
+// Background and sprites both draw with the configured shades; the default is the green tint
+static void test_dmg_palette() {
+    MMU mmu;
+    setup_dmg(mmu);
+    assert(mmu.get_ppu().get_palette() == PPU::DEFAULT_PALETTE);
+    fill_tile(mmu, 0, 1);                              // background: color 1
+    fill_tile(mmu, 1, 3);
+    set_sprite(mmu, 0, 16, 8, 1);                      // sprite over x 0-7: color 3
+    render_through_line(mmu, 0x93, 0);
+    assert(pixel(mmu, 0, 0) == 0xFF305030);
+    assert(pixel(mmu, 80, 0) == 0xFFCADFAA);
+
+    mmu.get_ppu().set_palette(PPU::GRAYSCALE_PALETTE);
+    mmu.write_byte(0xFF40, 0x00);
+    render_through_line(mmu, 0x93, 0);
+    assert(pixel(mmu, 0, 0) == 0xFF000000);
+    assert(pixel(mmu, 80, 0) == 0xFFAAAAAA);
+}
+
+// WX=3 puts the window 4 pixels left of the screen: its first 4 columns are never drawn
+static void test_window_wx_below_7() {
+    const std::array<uint32_t, 4> shades = {0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000};
+    MMU mmu;
+    setup_dmg(mmu);
+    mmu.get_ppu().set_palette(shades);
+    // Tile 2: columns 0-3 color 1, columns 4-7 color 3
+    for (int row = 0; row < 8; ++row) {
+        mmu.write_byte(0x8020 + row * 2, 0xFF);
+        mmu.write_byte(0x8020 + row * 2 + 1, 0x0F);
+    }
+    for (uint16_t address = 0x9C00; address < 0xA000; ++address) mmu.write_byte(address, 0x02); // window map
+    mmu.write_byte(0xFF4A, 0);  // WY
+    mmu.write_byte(0xFF4B, 3);  // WX
+    render_through_line(mmu, 0xF1, 0);
+
+    for (int x = 0; x < 4; ++x) assert(pixel(mmu, x, 0) == shades[3]);  // window columns 4-7
+    for (int x = 4; x < 8; ++x) assert(pixel(mmu, x, 0) == shades[1]);  // next tile, columns 0-3
+    for (int x = 8; x < 12; ++x) assert(pixel(mmu, x, 0) == shades[3]);
+}
+
+// Clearing LCDC bit 7 mid-frame takes effect on the write, not on the next PPU step
+static void test_lcd_off_mid_frame() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    lcd_on(mmu);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 50 && stat_mode(mmu) == 3; });
+    mmu.write_byte(0xFF40, 0x11);
+    assert(mmu.read_byte(0xFF44) == 0);
+    assert(stat_mode(mmu) == 0);
+    mmu.get_ppu().step(456, mmu);                               // stays there while off
+    assert(mmu.read_byte(0xFF44) == 0);
+    assert(stat_mode(mmu) == 0);
+}
+
+// Overlapping sprites: the lower X wins even from a later OAM slot. When the winner is behind
+// a non-zero BG pixel the BG shows, and the losing sprite must not show through it.
+static void test_overlapping_sprite_priority() {
+    const std::array<uint32_t, 4> shades = {0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000};
+    for (uint8_t winner_flags : {0x00, 0x80}) {
+        MMU mmu;
+        setup_dmg(mmu);
+        mmu.get_ppu().set_palette(shades);
+        fill_tile(mmu, 0, 1);                       // BG color 1 everywhere
+        fill_tile(mmu, 2, 2);
+        fill_tile(mmu, 3, 3);
+        set_sprite(mmu, 0, 16, 8 + 14, 2);          // x 14-21, OAM slot 0
+        set_sprite(mmu, 1, 16, 8 + 10, 3, winner_flags); // x 10-17, lower X
+        render_through_line(mmu, 0x93, 0);
+
+        uint32_t overlap = winner_flags ? shades[1] : shades[3];
+        for (int x = 10; x < 14; ++x) assert(pixel(mmu, x, 0) == overlap);
+        for (int x = 14; x < 18; ++x) assert(pixel(mmu, x, 0) == overlap);
+        for (int x = 18; x < 22; ++x) assert(pixel(mmu, x, 0) == shades[2]);
+    }
+}
+
+// On DMG, LCDC bit 0 also blanks the window: no window pixels and its line counter holds
+static void test_dmg_lcdc0_stops_window_line_counter() {
+    MMU mmu;
+    setup_dmg(mmu);
+    // Tile 2: row 0 color 3, row 1 color 1, the rest color 0
+    mmu.write_byte(0x8020, 0xFF);
+    mmu.write_byte(0x8021, 0xFF);
+    mmu.write_byte(0x8022, 0xFF);
+    for (uint16_t address = 0x9C00; address < 0xA000; ++address) mmu.write_byte(address, 0x02);
+    mmu.write_byte(0xFF4A, 72);
+    mmu.write_byte(0xFF4B, 7);
+    const uint8_t lcdc = 0xF1;
+
+    render_through_line(mmu, lcdc, 72);
+    uint32_t window_row0 = pixel(mmu, 0, 72);
+    mmu.write_byte(0xFF40, lcdc & ~0x01);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 75; });
+    mmu.write_byte(0xFF40, lcdc);
+    step_until(mmu, [&] { return mmu.read_byte(0xFF44) == 76; });
+    uint32_t window_row1 = pixel(mmu, 0, 75);
+    assert(window_row1 != window_row0);
+    for (int y = 73; y <= 74; ++y) {
+        for (int x = 0; x < 160; ++x) assert(pixel(mmu, x, y) != window_row0 && pixel(mmu, x, y) != window_row1);
+    }
+}
+
+// Pure red (BGR555 0x001F): full red without correction; Gambatte mixing mutes it and bleeds into blue
+static void test_cgb_color_to_argb() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    PPU& ppu = mmu.get_ppu();
+    assert(ppu.get_color_correction() == ColorCorrection::None);
+    assert(ppu.cgb_color_to_argb(0x001F) == 0xFFFF0000);
+    assert(ppu.cgb_color_to_argb(0x7FFF) == 0xFFFFFFFF);
+    ppu.set_color_correction(ColorCorrection::Gambatte);
+    assert(ppu.cgb_color_to_argb(0x001F) == 0xFFC9002E);     // r = 31*13/2, b = 31*3/2
+    assert(ppu.cgb_color_to_argb(0x0000) == 0xFF000000);
+}
+
+// The debug view covers the whole 32x32 map, including tiles outside the scrolled viewport
+static void test_render_full_background() {
+    const std::array<uint32_t, 4> shades = {0xFFFFFFFF, 0xFFAAAAAA, 0xFF555555, 0xFF000000};
+    MMU mmu;
+    setup_dmg(mmu);
+    mmu.get_ppu().set_palette(shades);
+    fill_tile(mmu, 3, 3);
+    mmu.write_byte(0x9800 + 25 * 32 + 20, 0x03);       // tile (20, 25) -> pixels (160-167, 200-207)
+    mmu.write_byte(0xFF42, 37);                        // SCY, SCX: ignored
+    mmu.write_byte(0xFF43, 91);
+    mmu.write_byte(0xFF40, 0x11);                      // tiles at 0x8000, map at 0x9800 (LCD still off)
+
+    std::vector<uint32_t> image = mmu.get_ppu().render_full_background(mmu);
+    assert(image.size() == 256 * 256);
+    for (int y = 0; y < 256; ++y) {
+        for (int x = 0; x < 256; ++x) {
+            bool tile = x >= 160 && x < 168 && y >= 200 && y < 208;
+            assert(image[y * 256 + x] == shades[tile ? 3 : 0]);
+        }
+    }
+}
+
+// A tile decoded through BGP and re-encoded by export_frame_2bpp comes back byte for byte,
+// including with a BGP that reorders the shades
+static void test_export_frame_2bpp_round_trip() {
+    const uint8_t tile[16] = {0x3C, 0x7E, 0x42, 0x42, 0x81, 0xFF, 0xA5, 0x5A,
+                              0xFF, 0x00, 0x00, 0xFF, 0x18, 0x24, 0x0F, 0xF0};
+    for (uint8_t bgp : {0xE4, 0x1B}) {
+        MMU mmu;
+        setup_dmg(mmu);
+        mmu.get_ppu().set_palette(PPU::GRAYSCALE_PALETTE);
+        for (int i = 0; i < 16; ++i) mmu.write_byte(0x8010 + i, tile[i]);
+        mmu.write_byte(0x9800 + 2 * 32 + 3, 0x01);          // screen tile (3, 2)
+        mmu.write_byte(0xFF47, bgp);
+        render_through_line(mmu, 0x91, 143);
+
+        std::vector<uint8_t> exported = mmu.get_ppu().export_frame_2bpp();
+        assert(exported.size() == 20 * 18 * 16);
+        size_t offset = (2 * 20 + 3) * 16;
+        for (int i = 0; i < 16; ++i) assert(exported[offset + i] == tile[i]);
+        for (int i = 0; i < 16; ++i) assert(exported[i] == 0x00);  // tile 0: color 0
+    }
+}
+
+// 12 sprites on one line: the OAM scan keeps the first 10 in OAM order; equal X keeps that order,
+// distinct X reorders those same 10 by X
+static void test_sprites_on_line_twelve() {
+    MMU mmu;
+    setup_dmg(mmu);
+    for (int i = 0; i < 12; ++i) set_sprite(mmu, i, 16 + 20, 50, 1);
+    std::vector<SpriteRenderer::Sprite> selected = mmu.get_ppu().sprites_on_line(mmu, 20);
+    assert(selected.size() == 10);
+    for (int i = 0; i < 10; ++i) assert(selected[i].oam_index == i);
+
+    for (int i = 0; i < 12; ++i) set_sprite(mmu, i, 16 + 20, static_cast<uint8_t>(120 - i * 8), 1);
+    selected = mmu.get_ppu().sprites_on_line(mmu, 20);
+    assert(selected.size() == 10);
+    for (int i = 0; i < 10; ++i) assert(selected[i].oam_index == 9 - i);    // lowest X first
+    assert(mmu.get_ppu().sprites_on_line(mmu, 19).empty());
+}
+
+int main() {
+    test_stat_lyc_interrupt();
+    test_stat_blocking();
+    test_cgb_palette_ram();
+    test_obj_disabled_hides_sprites();
+    test_vblank_raises_stat_mode1();
+    test_sprite_edge_clipping();
+    test_ten_sprites_per_line();
+    test_window_line_counter();
+    test_composited_frame_golden();
+    test_mode_timing_and_frame_length();
+    test_tall_sprite_tile_index();
+    test_mid_frame_bgp_change();
+    test_dmg_palette();
+    test_window_wx_below_7();
+    test_lcd_off_mid_frame();
+    test_overlapping_sprite_priority();
+    test_dmg_lcdc0_stops_window_line_counter();
+    test_cgb_color_to_argb();
+    test_render_full_background();
+    test_export_frame_2bpp_round_trip();
+    test_sprites_on_line_twelve();
+    std::printf("test_ppu: all tests passed\n");
+    return 0;
+}
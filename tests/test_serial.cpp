@@ -0,0 +1,96 @@
+#include "test_helpers.h"
+#include "emulator.h"
+
+// One internal-clock transfer: 8 bits at 8192 Hz
+static void run_serial_transfer(MMU& mmu) {
+    for (int i = 0; i < 4096 / 4; ++i) mmu.update_timer_cycles(4);
+}
+
+static void test_link_cable_exchange() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x18, 0xFE}); // JR -2: idle while the serial port works
+    std::string rom_path = write_rom_file("serial_idle.gb", rom);
+    Emulator master, slave;
+    master.set_headless(true);
+    slave.set_headless(true);
+    assert(master.load_rom(rom_path) && slave.load_rom(rom_path));
+    master.link(slave);
+    assert(master.is_linked() && slave.is_linked());
+
+    slave.debug_write_io(0xFF01, 0x5B);
+    slave.debug_write_io(0xFF02, 0x80);   // external clock: waits for the master
+    master.debug_write_io(0xFF01, 0xA4);
+    master.debug_write_io(0xFF0F, 0x00);
+    slave.debug_write_io(0xFF0F, 0x00);
+    master.debug_write_io(0xFF02, 0x81);  // internal clock: starts the transfer
+    for (int i = 0; i < 4096 / 4; ++i) master.step();
+
+    assert(master.debug_read_io(0xFF01) == 0x5B);
+    assert(slave.debug_read_io(0xFF01) == 0xA4);
+    assert((master.debug_read_io(0xFF02) & 0x80) == 0);
+    assert((slave.debug_read_io(0xFF02) & 0x80) == 0);
+    assert(master.debug_read_io(0xFF0F) & 0x08);
+    assert(slave.debug_read_io(0xFF0F) & 0x08);
+
+    // Destroying one side disconnects the other
+    {
+        Emulator temporary;
+        temporary.link(master);
+        assert(master.is_linked());
+    }
+    assert(!master.is_linked());
+}
+
+// Without a peer nothing answers: the master shifts in 0xFF
+static void test_unlinked_transfer_reads_ff() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF01, 0x42);
+    mmu.write_byte(0xFF02, 0x81);
+    run_serial_transfer(mmu);
+    assert(mmu.read_byte(0xFF01) == 0xFF);
+}
+
+// The callback sees every byte a program sends through SB/SC, in order
+static void test_serial_callback_receives_program_output() {
+    std::vector<uint8_t> rom = make_rom();
+    put_code(rom, 0x0150, {0x3E, 'O', 0xE0, 0x01,                 // LD A,'O' ; LDH (SB),A
+                           0x3E, 0x81, 0xE0, 0x02,                // LD A,$81 ; LDH (SC),A
+                           0xF0, 0x02, 0xCB, 0x7F, 0x20, 0xFA,    // $0158: wait for SC bit 7 to clear
+                           0x3E, 'K', 0xE0, 0x01,
+                           0x3E, 0x81, 0xE0, 0x02,
+                           0xF0, 0x02, 0xCB, 0x7F, 0x20, 0xFA,    // $0166
+                           0x18, 0xFE});                          // JR -2
+    Emulator emulator;
+    emulator.set_headless(true);
+    assert(emulator.load_rom(write_rom_file("serial_print.gb", rom)));
+    std::string received;
+    emulator.set_serial_callback([&](uint8_t byte) { received += static_cast<char>(byte); });
+    emulator.run_frame();
+    assert(received == "OK");
+}
+
+// An internal-clock transfer shifts 8 bits at 8192 Hz: done after exactly 4096 T-cycles,
+// with SC bit 7 cleared and IF bit 3 raised
+static void test_internal_clock_transfer_timing() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF0F, 0x00);
+    mmu.write_byte(0xFF01, 0x42);
+    mmu.write_byte(0xFF02, 0x81);
+    for (int i = 0; i < 4096 / 4 - 1; ++i) mmu.update_timer_cycles(4);
+    assert(mmu.read_byte(0xFF02) & 0x80);                          // 4 T-cycles to go
+    assert((mmu.read_byte(0xFF0F) & 0x08) == 0);
+    mmu.update_timer_cycles(4);
+    assert((mmu.read_byte(0xFF02) & 0x80) == 0);
+    assert(mmu.read_byte(0xFF0F) & 0x08);
+}
+
+int main() {
+    test_link_cable_exchange();
+    test_unlinked_transfer_reads_ff();
+    test_serial_callback_receives_program_output();
+    test_internal_clock_transfer_timing();
+    std::printf("test_serial: all tests passed\n");
+    return 0;
+}
@@ -0,0 +1,128 @@
+#include "test_helpers.h"
+
+static void test_div_counts_t_cycles() {
+    Timer timer;
+    // Odd-sized steps: remainders carry over instead of being dropped
+    for (int i = 0; i < 255; ++i) timer.update_cycles(3); // 765 cycles
+    assert(timer.get_divider() == 2);
+    timer.update_cycles(3);                                // 768
+    assert(timer.get_divider() == 3);
+    timer.set_divider(0x55);                               // any write resets DIV
+    assert(timer.get_divider() == 0);
+}
+
+static void test_tima_rates() {
+    // TAC clock select -> T-cycles per TIMA increment
+    const int periods[4] = {1024, 16, 64, 256};
+    for (uint8_t select = 0; select < 4; ++select) {
+        Timer timer;
+        timer.set_tac(0x04 | select);
+        for (int i = 0; i < periods[select] * 10; ++i) timer.update_cycles(1);
+        assert(timer.get_timer_counter() == 10);
+    }
+    // Disabled timer doesn't count
+    Timer stopped;
+    stopped.set_tac(0x01);
+    for (int i = 0; i < 100; ++i) stopped.update_cycles(16);
+    assert(stopped.get_timer_counter() == 0);
+}
+
+static void test_tima_overflow_reload_and_interrupt() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    mmu.write_byte(0xFF04, 0x00);
+    mmu.write_byte(0xFF06, 0xAB);  // TMA
+    mmu.write_byte(0xFF05, 0xFF);  // TIMA
+    mmu.write_byte(0xFF07, 0x05);  // enabled, 262144 Hz (every 16 T-cycles)
+    mmu.write_byte(0xFF0F, 0x00);
+    mmu.update_timer_cycles(16);   // overflow
+    assert(mmu.read_byte(0xFF05) == 0x00);
+    assert((mmu.read_byte(0xFF0F) & 0x04) == 0);
+    mmu.update_timer_cycles(3);    // still in the one M-cycle delay
+    assert(mmu.read_byte(0xFF05) == 0x00);
+    assert((mmu.read_byte(0xFF0F) & 0x04) == 0);
+    mmu.update_timer_cycles(1);    // reload from TMA and request the interrupt
+    assert(mmu.read_byte(0xFF05) == 0xAB);
+    assert(mmu.read_byte(0xFF0F) & 0x04);
+
+    // Writing TIMA during the delay cancels the reload and the interrupt
+    mmu.write_byte(0xFF0F, 0x00);
+    mmu.write_byte(0xFF04, 0x00);
+    mmu.write_byte(0xFF05, 0xFF);
+    mmu.update_timer_cycles(16);
+    mmu.write_byte(0xFF05, 0x10);
+    mmu.update_timer_cycles(8);
+    assert(mmu.read_byte(0xFF05) == 0x10);
+    assert((mmu.read_byte(0xFF0F) & 0x04) == 0);
+}
+
+// TIMA counts falling edges of the selected counter bit, so resetting DIV while it is 1 ticks TIMA
+static void test_div_reset_falling_edge() {
+    Timer timer;
+    timer.set_tac(0x05);           // bit 3
+    timer.update_cycles(8);        // bit 3 now set
+    timer.set_divider(0);
+    assert(timer.get_timer_counter() == 1);
+    timer.update_cycles(4);        // bit 3 clear
+    timer.set_divider(0);
+    assert(timer.get_timer_counter() == 1);
+}
+
+static void test_tac_05_reload_timing() {
+    Timer timer;
+    timer.set_timer_modulo(0x80);
+    timer.set_timer_counter(0xFE);
+    timer.set_tac(0x05);                   // 262144 Hz: one tick per 16 T-cycles
+    int reload_cycle = -1;
+    for (int cycle = 1; cycle <= 64 && reload_cycle < 0; ++cycle) {
+        if (timer.update_cycles(1)) reload_cycle = cycle;
+        if (cycle == 32) assert(timer.get_timer_counter() == 0x00); // overflowed on the 2nd tick
+    }
+    assert(reload_cycle == 36);            // 2 ticks + 4 T-cycle delay
+    assert(timer.get_timer_counter() == 0x80);
+    for (int i = 0; i < 16; ++i) timer.update_cycles(1);
+    assert(timer.get_timer_counter() == 0x81); // keeps counting from TMA
+}
+
+// Switching TAC from a fast to a slow rate while the old bit is 1 and the new bit is 0 is a falling edge: TIMA ticks
+static void test_tac_change_falling_edge() {
+    Timer timer;
+    timer.set_tac(0x05);           // bit 3 (262144 Hz)
+    timer.update_cycles(8);        // counter = 8: bit 3 set, bit 9 clear
+    timer.set_tac(0x04);           // bit 9 (4096 Hz)
+    assert(timer.get_timer_counter() == 1);
+
+    // Both bits clear: no spurious increment
+    Timer quiet;
+    quiet.set_tac(0x05);
+    quiet.update_cycles(4);
+    quiet.set_tac(0x04);
+    assert(quiet.get_timer_counter() == 0);
+}
+
+// Writing 0x55 to 0xFF04 through the MMU reads back 0 and restarts the internal counter:
+// the next DIV tick is a full 256 T-cycles later
+static void test_mmu_div_write_resets_counter() {
+    MMU mmu;
+    mmu.load_rom(make_rom());
+    for (int i = 0; i < 200; ++i) mmu.update_timer_cycles(4);    // DIV = 3, counter mid-period
+    assert(mmu.read_byte(0xFF04) == 3);
+    mmu.write_byte(0xFF04, 0x55);
+    assert(mmu.read_byte(0xFF04) == 0x00);
+    for (int i = 0; i < 63; ++i) mmu.update_timer_cycles(4);     // 252
+    assert(mmu.read_byte(0xFF04) == 0x00);
+    mmu.update_timer_cycles(4);                                  // 256
+    assert(mmu.read_byte(0xFF04) == 0x01);
+}
+
+int main() {
+    test_div_counts_t_cycles();
+    test_tima_rates();
+    test_tima_overflow_reload_and_interrupt();
+    test_div_reset_falling_edge();
+    test_tac_05_reload_timing();
+    test_tac_change_falling_edge();
+    test_mmu_div_write_resets_counter();
+    std::printf("test_timer: all tests passed\n");
+    return 0;
+}